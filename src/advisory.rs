@@ -0,0 +1,208 @@
+//! Security advisories from a local clone of the RustSec advisory database
+//!
+//! Advisories are markdown files in `crates/<name>/`, starting with a TOML block that says which
+//! versions are patched or unaffected. Every other version is affected.
+
+use {
+    crate::{lockfile::cargo_home, project::Pkg},
+    anyhow::{bail, Context},
+    cargo_metadata::semver::{Version, VersionReq},
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+    },
+};
+
+/// Where `cargo audit` keeps its clone of the advisory database
+pub fn default_db_path() -> Option<PathBuf> {
+    cargo_home().map(|home| home.join("advisory-db").into_std_path_buf())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AdvisoryKind {
+    Vulnerability,
+    /// Informational: Code that can cause undefined behavior from safe code
+    Unsound,
+    /// Informational: The crate isn't maintained anymore
+    Unmaintained,
+    /// Informational: The affected versions were yanked
+    Yanked,
+    /// Informational: Anything else worth knowing
+    Notice,
+}
+
+impl AdvisoryKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Vulnerability => "vulnerability",
+            Self::Unsound => "unsound",
+            Self::Unmaintained => "unmaintained",
+            Self::Yanked => "yanked",
+            Self::Notice => "notice",
+        }
+    }
+    pub fn is_informational(self) -> bool {
+        self != Self::Vulnerability
+    }
+}
+
+pub struct Advisory {
+    /// Like `RUSTSEC-2021-0001`
+    pub id: String,
+    pub package: String,
+    pub title: String,
+    /// Markdown
+    pub description: String,
+    pub date: String,
+    pub url: Option<String>,
+    /// Other ids of the advisory, like CVE numbers
+    pub aliases: Vec<String>,
+    pub kind: AdvisoryKind,
+    pub patched: Vec<VersionReq>,
+    pub unaffected: Vec<VersionReq>,
+}
+
+impl Advisory {
+    pub fn affects(&self, version: &Version) -> bool {
+        !self.patched.iter().chain(&self.unaffected).any(|req| req.matches(version))
+    }
+    /// The advisory's page on rustsec.org
+    pub fn link(&self) -> String {
+        format!("https://rustsec.org/advisories/{}.html", self.id)
+    }
+    fn parse(src: &str) -> anyhow::Result<Option<Self>> {
+        #[derive(serde::Deserialize)]
+        struct AdvisoryFile {
+            advisory: Metadata,
+            #[serde(default)]
+            versions: Versions,
+        }
+        #[derive(serde::Deserialize)]
+        struct Metadata {
+            id: String,
+            package: String,
+            date: String,
+            url: Option<String>,
+            #[serde(default)]
+            aliases: Vec<String>,
+            informational: Option<String>,
+            withdrawn: Option<String>,
+        }
+        #[derive(serde::Deserialize, Default)]
+        struct Versions {
+            #[serde(default)]
+            patched: Vec<String>,
+            #[serde(default)]
+            unaffected: Vec<String>,
+        }
+        let rest = src.trim_start().strip_prefix("```toml").context("No TOML front matter")?;
+        let (front, body) = rest.split_once("\n```").context("Unclosed TOML front matter")?;
+        let file: AdvisoryFile = toml::from_str(front)?;
+        if file.advisory.withdrawn.is_some() {
+            return Ok(None);
+        }
+        let parse_reqs = |reqs: Vec<String>| -> anyhow::Result<Vec<VersionReq>> {
+            reqs.iter()
+                .map(|req| {
+                    VersionReq::parse(req).with_context(|| format!("Invalid requirement `{req}`"))
+                })
+                .collect()
+        };
+        let body = body.trim();
+        let (title, description) = match body.strip_prefix("# ") {
+            Some(body) => body.split_once('\n').unwrap_or((body, "")),
+            None => ("", body),
+        };
+        let kind = match file.advisory.informational.as_deref() {
+            None => AdvisoryKind::Vulnerability,
+            Some("unsound") => AdvisoryKind::Unsound,
+            Some("unmaintained") => AdvisoryKind::Unmaintained,
+            Some("yanked") => AdvisoryKind::Yanked,
+            Some(_) => AdvisoryKind::Notice,
+        };
+        Ok(Some(Self {
+            id: file.advisory.id,
+            package: file.advisory.package,
+            title: title.trim().to_owned(),
+            description: description.trim().to_owned(),
+            date: file.advisory.date,
+            url: file.advisory.url,
+            aliases: file.advisory.aliases,
+            kind,
+            patched: parse_reqs(file.versions.patched)?,
+            unaffected: parse_reqs(file.versions.unaffected)?,
+        }))
+    }
+}
+
+/// The advisories of a RustSec advisory database checkout
+pub struct AdvisoryDb {
+    advisories: Vec<Advisory>,
+    /// Indices into `advisories` by package name
+    by_package: HashMap<String, Vec<usize>>,
+    /// Files that couldn't be read, with the reason
+    pub skipped: Vec<String>,
+}
+
+impl AdvisoryDb {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let crates_dir = path.join("crates");
+        if !crates_dir.is_dir() {
+            bail!(
+                "{} is not an advisory database (no `crates` directory)",
+                path.display()
+            );
+        }
+        let mut db = Self {
+            advisories: Vec::new(),
+            by_package: HashMap::new(),
+            skipped: Vec::new(),
+        };
+        for crate_dir in std::fs::read_dir(&crates_dir)?.flatten() {
+            let Ok(files) = std::fs::read_dir(crate_dir.path()) else {
+                continue;
+            };
+            for file in files.flatten() {
+                let path = file.path();
+                if path.extension().is_none_or(|ext| ext != "md") {
+                    continue;
+                }
+                let parsed = std::fs::read_to_string(&path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|src| Advisory::parse(&src));
+                match parsed {
+                    Ok(Some(advisory)) => {
+                        db.by_package
+                            .entry(advisory.package.clone())
+                            .or_default()
+                            .push(db.advisories.len());
+                        db.advisories.push(advisory);
+                    }
+                    Ok(None) => {}
+                    Err(e) => db.skipped.push(format!("{}: {e:#}", path.display())),
+                }
+            }
+        }
+        Ok(db)
+    }
+    pub fn len(&self) -> usize {
+        self.advisories.len()
+    }
+    /// The advisories that affect `pkg`, newest first. Only crates.io packages are checked,
+    /// as that's what the database is about.
+    pub fn for_pkg(&self, pkg: &Pkg) -> Vec<&Advisory> {
+        if !pkg.cm_pkg.source.as_ref().is_some_and(|source| source.is_crates_io()) {
+            return Vec::new();
+        }
+        let Some(indices) = self.by_package.get(pkg.cm_pkg.name.as_str()) else {
+            return Vec::new();
+        };
+        let mut advisories: Vec<&Advisory> = indices
+            .iter()
+            .map(|idx| &self.advisories[*idx])
+            .filter(|advisory| advisory.affects(&pkg.cm_pkg.version))
+            .collect();
+        advisories.sort_by(|a, b| b.date.cmp(&a.date));
+        advisories
+    }
+}
@@ -1,21 +1,63 @@
 use {
-    crate::{config::Config, project::Project, ui::Gui},
+    crate::{
+        config::Config,
+        project::Project,
+        ui::{Gui, NavState, OpenProjectInfo},
+        watch::FileWatcher,
+    },
     anyhow::Context,
     directories::ProjectDirs,
     eframe::egui,
-    std::{path::PathBuf, sync::mpsc},
+    std::{
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            mpsc, Arc,
+        },
+        time::Instant,
+    },
 };
 
 pub struct App {
-    pub project: Option<Project>,
+    /// There is always at least one tab
+    pub tabs: Vec<ProjectTab>,
+    /// Index of the tab currently being shown
+    pub active_tab: usize,
     pub gui: Gui,
     pub dirs: ProjectDirs,
     pub config: Config,
+}
+
+/// An open project, along with its loading and navigation state
+#[derive(Default)]
+pub struct ProjectTab {
+    pub project: Option<Project>,
     pub load: Option<LoadState>,
+    /// Path and arguments of the last load, used for reloading
+    pub last_load: Option<(PathBuf, crate::Args)>,
+    /// Watches the files of the loaded project, to reload it when they change
+    pub watcher: Option<FileWatcher>,
+    /// Path and arguments of the open project while a different one is being opened in its place.
+    ///
+    /// The open project stays until the other one has loaded, and is kept if that fails.
+    pub replaced_load: Option<(PathBuf, crate::Args)>,
+    /// Navigation state of this tab while it's in the background.
+    ///
+    /// The active tab's navigation state lives in [`Gui::nav`].
+    pub nav: NavState,
 }
 
 pub enum LoadStage {
     MetadataQuery,
+    /// A line of cargo's stderr output
+    CargoOutput(String),
+    /// Progress of the current stage
+    Progress {
+        done: usize,
+        total: usize,
+    },
+    ReadMetadataJson,
+    ReadLockfile,
     Finished(Project),
     Error(anyhow::Error),
     PkgInfoCollect,
@@ -31,6 +73,89 @@ pub struct LoadState {
     pub(crate) path: PathBuf,
     /// User-facing load status message
     pub(crate) msg: String,
+    /// Last line of output from cargo
+    pub(crate) cargo_output: String,
+    pub(crate) progress: Option<(usize, usize)>,
+    pub(crate) started: Instant,
+    pub(crate) stage_started: Instant,
+    /// Set to request the loading thread to stop
+    pub(crate) cancel: Arc<AtomicBool>,
+}
+
+impl LoadState {
+    /// Run `load` on a new thread, and track its progress
+    pub(crate) fn spawn(
+        path: PathBuf,
+        load: impl FnOnce(&LoadSend, &AtomicBool) -> anyhow::Result<()> + Send + 'static,
+    ) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let thread_cancel = cancel.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = load(&tx, &thread_cancel) {
+                // If the load was cancelled, nobody is listening anymore
+                let _ = tx.send(LoadStage::Error(e));
+            }
+        });
+        Self {
+            recv: rx,
+            path,
+            msg: "Preparing...".into(),
+            cargo_output: String::new(),
+            progress: None,
+            started: Instant::now(),
+            stage_started: Instant::now(),
+            cancel,
+        }
+    }
+    /// Process the stages received so far.
+    ///
+    /// Returns the outcome when a project finished loading, or loading failed.
+    pub(crate) fn poll(&mut self) -> Option<anyhow::Result<Project>> {
+        loop {
+            match self.recv.try_recv() {
+                Ok(stage) => match stage {
+                    LoadStage::Finished(proj) => return Some(Ok(proj)),
+                    LoadStage::Error(err) => return Some(Err(err)),
+                    LoadStage::MetadataQuery => self.set_stage("Querying metadata..."),
+                    LoadStage::CargoOutput(line) => self.cargo_output = line,
+                    LoadStage::Progress { done, total } => self.progress = Some((done, total)),
+                    LoadStage::ReadMetadataJson => self.set_stage("Reading metadata json..."),
+                    LoadStage::ReadLockfile => self.set_stage("Reading lockfile..."),
+                    LoadStage::PkgInfoCollect => self.set_stage("Collecting package info..."),
+                    LoadStage::Resolve => self.set_stage("Resolving dependencies..."),
+                    LoadStage::GenDepGraph => self.set_stage("Generating dependency graph..."),
+                },
+                Err(mpsc::TryRecvError::Empty) => return None,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.msg = "Error loading: Channel disconnected.".into();
+                    return None;
+                }
+            }
+        }
+    }
+    /// Begin a new load stage with the status message `msg`
+    pub(crate) fn set_stage(&mut self, msg: &str) {
+        msg.clone_into(&mut self.msg);
+        self.progress = None;
+        self.stage_started = Instant::now();
+    }
+    /// The status message along with progress and elapsed time
+    pub(crate) fn status(&self) -> String {
+        let mut status = self.msg.clone();
+        if let Some((done, total)) = self.progress {
+            status.push_str(&format!(" {done}/{total}"));
+        }
+        status.push_str(&format!(
+            " ({:.1}s, {:.1}s total)",
+            self.stage_started.elapsed().as_secs_f32(),
+            self.started.elapsed().as_secs_f32()
+        ));
+        status
+    }
+    pub(crate) fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
 }
 
 impl App {
@@ -40,11 +165,11 @@ impl App {
         let config = Config::load_or_default(dirs.config_dir());
         let style_name = config.style_name.clone();
         let mut app = App {
-            project: None,
+            tabs: vec![ProjectTab::default()],
+            active_tab: 0,
             gui: Gui::new(egui_ctx),
             dirs,
             config,
-            load: None,
         };
         match crate::style::style_fun_by_name(&style_name) {
             Some(fun) => {
@@ -57,18 +182,151 @@ impl App {
         Ok(app)
     }
 
+    /// Load a project into the active tab
     pub(crate) fn load_project_async(&mut self, path: PathBuf, args: crate::Args) {
-        let (tx, rx) = mpsc::channel();
-        self.load = Some(LoadState {
-            recv: rx,
-            path: path.clone(),
-            msg: "Preparing...".into(),
-        });
-        std::thread::spawn(move || {
-            if let Err(e) = Project::load(&path, &args, tx.clone()) {
-                tx.send(LoadStage::Error(e)).unwrap();
+        self.tabs[self.active_tab].load_async(path, args);
+    }
+    /// Open a different project in the active tab, given a project directory or manifest path
+    pub(crate) fn open_project(&mut self, path: PathBuf) {
+        let tab = &mut self.tabs[self.active_tab];
+        // Keep the session's options (features, target, toolchain...), only the project changes
+        let mut args = tab.last_load.as_ref().map(|(_, args)| args.clone()).unwrap_or_default();
+        args.manifest_path = Some(path.clone());
+        args.metadata_json = None;
+        let current = tab.last_load.clone().filter(|_| tab.project.is_some());
+        tab.load_async(path, args);
+        tab.replaced_load = tab.replaced_load.take().or(current);
+    }
+    pub(crate) fn new_tab(&mut self) {
+        self.tabs.push(ProjectTab::default());
+        self.switch_tab(self.tabs.len() - 1);
+    }
+    pub(crate) fn switch_tab(&mut self, idx: usize) {
+        self.gui.settings_window.resolve_draft = None;
+        std::mem::swap(&mut self.gui.nav, &mut self.tabs[self.active_tab].nav);
+        self.active_tab = idx;
+        std::mem::swap(&mut self.gui.nav, &mut self.tabs[self.active_tab].nav);
+        self.update_other_projects();
+    }
+    pub(crate) fn close_tab(&mut self, idx: usize) {
+        if self.tabs.len() == 1 {
+            self.tabs[0] = ProjectTab::default();
+            self.gui.nav = NavState::default();
+        } else {
+            // Park the active navigation state, so it moves along with its tab
+            std::mem::swap(&mut self.gui.nav, &mut self.tabs[self.active_tab].nav);
+            self.tabs.remove(idx);
+            if self.active_tab > idx || self.active_tab == self.tabs.len() {
+                self.active_tab -= 1;
             }
-        });
+            std::mem::swap(&mut self.gui.nav, &mut self.tabs[self.active_tab].nav);
+        }
+        self.update_other_projects();
+    }
+    /// Make `new` the project of tab `idx`. If it replaces a previous load,
+    /// the package selection is carried over.
+    pub(crate) fn set_project(&mut self, idx: usize, mut new: Project, ctx: &egui::Context) {
+        let is_active = idx == self.active_tab;
+        let tab = &mut self.tabs[idx];
+        tab.watcher = tab.can_reload().then(|| FileWatcher::new(new.graph_input_files(), ctx));
+        let nav = if is_active {
+            &mut self.gui.nav
+        } else {
+            &mut tab.nav
+        };
+        if tab.replaced_load.take().is_some() {
+            // A different project, so there is nothing to carry over
+            tab.project = None;
+        }
+        match tab.project.take() {
+            Some(mut old) => {
+                new.set_target(old.target.take());
+                nav.carry_over_selection(&old, &new);
+                nav.last_changes = Some(new.diff(&old));
+                if is_active {
+                    self.gui.show_toast(new.change_summary(&old));
+                }
+            }
+            None => nav.reset_for_project(&new),
+        }
+        if let Some((path, args)) = &tab.last_load {
+            // Saved metadata isn't a project that can be reopened
+            if args.metadata_json.is_none() {
+                let path = std::path::absolute(path).unwrap_or_else(|_| path.clone());
+                self.config.add_recent_project(path);
+            }
+        }
+        tab.project = Some(new);
+        self.update_other_projects();
+    }
+    /// Refresh the info the ui uses to tell which other open projects contain a package
+    fn update_other_projects(&mut self) {
+        self.gui.other_projects = self
+            .tabs
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| *idx != self.active_tab)
+            .filter_map(|(idx, tab)| {
+                let project = tab.project.as_ref()?;
+                Some(OpenProjectInfo {
+                    tab: idx,
+                    title: tab.title(),
+                    versions: project.versions_by_name(),
+                })
+            })
+            .collect();
+    }
+}
+
+impl ProjectTab {
+    pub(crate) fn load_async(&mut self, path: PathBuf, args: crate::Args) {
+        self.last_load = Some((path.clone(), args.clone()));
+        if let Some(load) = &self.load {
+            load.cancel();
+        }
+        self.load = Some(LoadState::spawn(path.clone(), move |tx, cancel| {
+            Project::load(&path, &args, tx.clone(), cancel)
+        }));
+    }
+    /// Give up on the load in progress. If it was opening a different project,
+    /// the open one stays.
+    pub(crate) fn abandon_load(&mut self) {
+        if let Some(load) = self.load.take() {
+            load.cancel();
+        }
+        if let Some(last_load) = self.replaced_load.take() {
+            self.last_load = Some(last_load);
+        }
+    }
+    /// Whether the project can be loaded again. Metadata read from stdin can't.
+    pub(crate) fn can_reload(&self) -> bool {
+        self.last_load.as_ref().is_some_and(|(_, args)| {
+            args.metadata_json.as_deref() != Some(std::path::Path::new("-"))
+        })
+    }
+    /// Load the project again, keeping the ui state where possible
+    pub(crate) fn reload(&mut self) {
+        if !self.can_reload() {
+            return;
+        }
+        if let Some((path, args)) = self.last_load.clone() {
+            self.load_async(path, args);
+        }
+    }
+    pub fn title(&self) -> String {
+        if let Some(name) = self.project.as_ref().and_then(|proj| {
+            let key = proj.initial_pkg()?;
+            Some(proj.packages[key].cm_pkg.name.clone())
+        }) {
+            return name;
+        }
+        match &self.last_load {
+            Some((path, _)) => path.file_name().map_or_else(
+                || path.display().to_string(),
+                |name| name.to_string_lossy().into(),
+            ),
+            None => "New tab".into(),
+        }
     }
 }
 
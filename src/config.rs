@@ -1,9 +1,58 @@
-use std::path::Path;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
 #[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub style_name: String,
     pub terminal_app: String,
+    /// Recently opened projects, most recent first
+    pub recent_projects: Vec<PathBuf>,
+    /// Named feature selections, per workspace root
+    pub feature_presets: BTreeMap<PathBuf, Vec<FeaturePreset>>,
+    /// License policy files chosen instead of the default one, per workspace root
+    pub license_policies: BTreeMap<PathBuf, PathBuf>,
+    /// Templates for third-party notices chosen instead of the built-in ones, per workspace root
+    pub notices_templates: BTreeMap<PathBuf, PathBuf>,
+    /// Path of the RustSec advisory database checkout, instead of `~/.cargo/advisory-db`
+    pub advisory_db: Option<PathBuf>,
+}
+
+/// A named set of feature flags to resolve a project with
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Default)]
+pub struct FeaturePreset {
+    pub name: String,
+    #[serde(default)]
+    pub features: Vec<String>,
+    #[serde(default)]
+    pub no_default_features: bool,
+    #[serde(default)]
+    pub all_features: bool,
+}
+
+impl FeaturePreset {
+    pub fn from_args(name: String, args: &crate::ResolveArgs) -> Self {
+        Self {
+            name,
+            features: args.features.clone(),
+            no_default_features: args.no_default_features,
+            all_features: args.all_features,
+        }
+    }
+    /// Whether `args` select the same features as this preset
+    pub fn matches(&self, args: &crate::ResolveArgs) -> bool {
+        self.features == args.features
+            && self.no_default_features == args.no_default_features
+            && self.all_features == args.all_features
+    }
+    /// Apply the feature selection of this preset to `args`
+    pub fn apply(&self, args: &mut crate::ResolveArgs) {
+        args.features = self.features.clone();
+        args.no_default_features = self.no_default_features;
+        args.all_features = self.all_features;
+    }
 }
 
 impl Default for Config {
@@ -11,11 +60,17 @@ impl Default for Config {
         Self {
             style_name: "crates.io".into(),
             terminal_app: String::new(),
+            recent_projects: Vec::new(),
+            feature_presets: BTreeMap::new(),
+            license_policies: BTreeMap::new(),
+            notices_templates: BTreeMap::new(),
+            advisory_db: None,
         }
     }
 }
 
 const FILENAME: &str = "config.toml";
+const MAX_RECENT_PROJECTS: usize = 10;
 
 impl Config {
     fn load(dir: &Path) -> anyhow::Result<Self> {
@@ -32,6 +87,19 @@ impl Config {
             }
         }
     }
+    pub fn add_recent_project(&mut self, path: PathBuf) {
+        self.recent_projects.retain(|recent| *recent != path);
+        self.recent_projects.insert(0, path);
+        self.recent_projects.truncate(MAX_RECENT_PROJECTS);
+    }
+    /// Save `preset` for the project at `root`, replacing any preset with the same name
+    pub fn save_feature_preset(&mut self, root: PathBuf, preset: FeaturePreset) {
+        let presets = self.feature_presets.entry(root).or_default();
+        match presets.iter_mut().find(|p| p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => presets.push(preset),
+        }
+    }
     pub fn save(&self, dir: &Path) -> anyhow::Result<()> {
         if !dir.exists() {
             std::fs::create_dir_all(dir)?;
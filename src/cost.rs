@@ -0,0 +1,172 @@
+//! What each direct dependency brings into the dependency graph
+
+use {
+    crate::project::{PkgKey, Project},
+    cargo_metadata::semver::Version,
+    std::{collections::HashSet, path::Path},
+};
+
+/// The cost of a direct dependency, see [`direct_dep_costs`]
+pub struct DepCost {
+    pub dep: PkgKey,
+    /// Packages that would disappear from the graph if the dependency was removed
+    pub exclusive: Vec<PkgKey>,
+    /// Total size of the sources of the exclusive packages, in bytes.
+    /// `None` until measured with [`source_size`], which can take a while.
+    pub source_size: Option<u64>,
+    /// Exclusive packages with a build script
+    pub build_scripts: usize,
+    /// Exclusive packages that are proc-macros
+    pub proc_macros: usize,
+}
+
+/// Packages that get built for the workspace members, leaving out the edge `skip`.
+///
+/// Dev dependencies are only followed from workspace members, like cargo does.
+fn built_packages(project: &Project, skip: Option<(PkgKey, PkgKey)>) -> HashSet<PkgKey> {
+    let mut seen: HashSet<PkgKey> = project.workspace_members.iter().copied().collect();
+    let mut stack = project.workspace_members.clone();
+    while let Some(key) = stack.pop() {
+        let member = project.is_workspace_member(key);
+        for link in &project.packages[key].dependencies {
+            if skip == Some((key, link.pkg_key)) || !project.link_on_target(link) {
+                continue;
+            }
+            let followed = member || !link.is_dev_only();
+            if followed && seen.insert(link.pkg_key) {
+                stack.push(link.pkg_key);
+            }
+        }
+    }
+    seen
+}
+
+/// The cost of each direct dependency of `root`, without the source sizes
+pub fn direct_dep_costs(project: &Project, root: PkgKey) -> Vec<DepCost> {
+    let all = built_packages(project, None);
+    let mut costs = Vec::new();
+    for dep in project.packages[root].dependencies.iter().map(|link| link.pkg_key) {
+        let without = built_packages(project, Some((root, dep)));
+        let exclusive: Vec<PkgKey> = all.difference(&without).copied().collect();
+        let pkgs = exclusive.iter().map(|key| &project.packages[*key]);
+        costs.push(DepCost {
+            dep,
+            source_size: None,
+            build_scripts: pkgs
+                .clone()
+                .filter(|pkg| pkg.cm_pkg.targets.iter().any(|target| target.is_custom_build()))
+                .count(),
+            proc_macros: pkgs
+                .filter(|pkg| pkg.cm_pkg.targets.iter().any(|target| target.is_proc_macro()))
+                .count(),
+            exclusive,
+        });
+    }
+    costs
+}
+
+/// What enabling a feature adds to a package's dependencies, see [`feature_cost`]
+pub struct FeatureCost {
+    pub feature: String,
+    /// Name and version of the packages that are only there with the feature
+    pub extra: Vec<(String, Version)>,
+    /// Extra packages with a build script
+    pub build_scripts: usize,
+    /// Total size of the sources of the extra packages, in bytes
+    pub source_size: u64,
+}
+
+/// Packages that users of `root` build: everything reachable through normal and build dependencies
+pub fn downstream_packages(project: &Project, root: PkgKey) -> HashSet<PkgKey> {
+    let mut seen = HashSet::from([root]);
+    let mut stack = vec![root];
+    while let Some(key) = stack.pop() {
+        for link in &project.packages[key].dependencies {
+            let followed = project.link_on_target(link) && !link.is_dev_only();
+            if followed && seen.insert(link.pkg_key) {
+                stack.push(link.pkg_key);
+            }
+        }
+    }
+    seen
+}
+
+/// Name and version of the downstream packages of `root`, to compare across resolves
+pub fn downstream_ids(project: &Project, root: PkgKey) -> HashSet<(String, Version)> {
+    downstream_packages(project, root)
+        .into_iter()
+        .map(|key| {
+            let pkg = &project.packages[key].cm_pkg;
+            (pkg.name.clone(), pkg.version.clone())
+        })
+        .collect()
+}
+
+/// The cost of `feature` of `root`, with `project` resolved with only that feature enabled,
+/// and `minimal` the downstream packages without any features
+pub fn feature_cost(
+    feature: String,
+    minimal: &HashSet<(String, Version)>,
+    project: &Project,
+    root: PkgKey,
+) -> FeatureCost {
+    let extra_keys: Vec<PkgKey> = downstream_packages(project, root)
+        .into_iter()
+        .filter(|key| {
+            let pkg = &project.packages[*key].cm_pkg;
+            !minimal.contains(&(pkg.name.clone(), pkg.version.clone()))
+        })
+        .collect();
+    let pkgs = extra_keys.iter().map(|key| &project.packages[*key]);
+    let mut extra: Vec<(String, Version)> = pkgs
+        .clone()
+        .map(|pkg| (pkg.cm_pkg.name.clone(), pkg.cm_pkg.version.clone()))
+        .collect();
+    extra.sort();
+    FeatureCost {
+        feature,
+        extra,
+        build_scripts: pkgs
+            .clone()
+            .filter(|pkg| pkg.cm_pkg.targets.iter().any(|target| target.is_custom_build()))
+            .count(),
+        source_size: source_size(pkgs.map(|pkg| pkg.manifest_dir.as_std_path())),
+    }
+}
+
+/// A size in bytes, in human readable units
+pub fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{bytes} B"),
+        1024..1_048_576 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+/// Total size of the sources in `dirs`, in bytes
+pub fn source_size<'a>(dirs: impl IntoIterator<Item = &'a Path>) -> u64 {
+    dirs.into_iter().map(dir_size).sum()
+}
+
+/// Total size of the files in `dir`, leaving out build output and hidden directories
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut size = 0;
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            let name = entry.file_name();
+            if name == "target" || name.to_string_lossy().starts_with('.') {
+                continue;
+            }
+            size += dir_size(&entry.path());
+        } else if let Ok(meta) = entry.metadata() {
+            size += meta.len();
+        }
+    }
+    size
+}
@@ -0,0 +1,303 @@
+//! Crates that are in the dependency graph with more than one version
+
+use {
+    crate::project::{PkgKey, Project},
+    cargo_metadata::semver::{Version, VersionReq},
+    std::collections::HashMap,
+};
+
+/// A crate name that resolved to more than one version
+pub struct Duplicate {
+    pub name: String,
+    /// Sorted by version, oldest first
+    pub versions: Vec<DuplicateVersion>,
+    /// The least amount of requirement changes that would leave only one version
+    pub suggestion: Option<Suggestion>,
+}
+
+pub struct DuplicateVersion {
+    pub key: PkgKey,
+    pub dependents: Vec<Requirement>,
+}
+
+/// A dependent's requirement on a package
+pub struct Requirement {
+    pub dependent: PkgKey,
+    /// `None` if the dependency declaration isn't known, like with `--lockfile-only`
+    pub req: Option<VersionReq>,
+}
+
+/// Unifying the versions of a duplicate on one of them
+pub struct Suggestion {
+    /// The version to unify on
+    pub target: PkgKey,
+    pub changes: Vec<ReqChange>,
+}
+
+/// A dependent whose requirement has to change for a [`Suggestion`]
+pub struct ReqChange {
+    pub dependent: PkgKey,
+    /// The version the dependent currently uses
+    pub current: PkgKey,
+    pub old_req: VersionReq,
+    /// The new requirement, or `None` if the old one already allows the target version,
+    /// and only the lockfile has to be updated
+    pub new_req: Option<String>,
+}
+
+/// The smallest caret requirement that allows `version`, like `^0.5`
+fn caret_req(version: &Version) -> String {
+    match (version.major, version.minor) {
+        (0, 0) => format!("^0.0.{}", version.patch),
+        (0, minor) => format!("^0.{minor}"),
+        (major, minor) => format!("^{major}.{minor}"),
+    }
+}
+
+/// Every crate name with more than one version, sorted by name
+pub fn find_duplicates(project: &Project) -> Vec<Duplicate> {
+    let mut by_name: HashMap<&str, Vec<PkgKey>> = HashMap::new();
+    for (key, pkg) in &project.packages {
+        by_name.entry(&pkg.cm_pkg.name).or_default().push(key);
+    }
+    let mut duplicates: Vec<Duplicate> = by_name
+        .into_iter()
+        .filter(|(_, keys)| keys.len() > 1)
+        .map(|(name, mut keys)| {
+            keys.sort_by(|a, b| {
+                project.packages[*a].cm_pkg.version.cmp(&project.packages[*b].cm_pkg.version)
+            });
+            let versions: Vec<DuplicateVersion> = keys
+                .into_iter()
+                .map(|key| DuplicateVersion {
+                    key,
+                    dependents: requirements(project, key),
+                })
+                .collect();
+            Duplicate {
+                name: name.to_owned(),
+                suggestion: suggest(project, &versions),
+                versions,
+            }
+        })
+        .collect();
+    duplicates.sort_by(|a, b| a.name.cmp(&b.name));
+    duplicates
+}
+
+/// The requirements of the dependents of `key` that resolved to it
+fn requirements(project: &Project, key: PkgKey) -> Vec<Requirement> {
+    let mut reqs = Vec::new();
+    for link in &project.packages[key].dependents {
+        let dependent = &project.packages[link.pkg_key];
+        let before = reqs.len();
+        for decl in &dependent.cm_pkg.dependencies {
+            if dependent.resolved_dep(decl, &project.packages).map(|pkg| pkg.key) != Some(key) {
+                continue;
+            }
+            if !reqs.iter().any(|req: &Requirement| {
+                req.dependent == dependent.key && req.req.as_ref() == Some(&decl.req)
+            }) {
+                reqs.push(Requirement {
+                    dependent: dependent.key,
+                    req: Some(decl.req.clone()),
+                });
+            }
+        }
+        if reqs.len() == before {
+            reqs.push(Requirement {
+                dependent: dependent.key,
+                req: None,
+            });
+        }
+    }
+    reqs.sort_by(|a, b| {
+        let (a, b) = (
+            &project.packages[a.dependent].cm_pkg,
+            &project.packages[b.dependent].cm_pkg,
+        );
+        (&a.name, &a.version).cmp(&(&b.name, &b.version))
+    });
+    reqs
+}
+
+/// Find the version that needs the fewest requirement changes to unify on.
+///
+/// Lowering a requirement is usually not possible without code changes, so those count the most,
+/// then raising requirements, then lockfile updates. Ties go to the newer version.
+fn suggest(project: &Project, versions: &[DuplicateVersion]) -> Option<Suggestion> {
+    let mut best: Option<((usize, usize, usize), Suggestion)> = None;
+    for candidate in versions.iter().rev() {
+        let target_version = &project.packages[candidate.key].cm_pkg.version;
+        let mut changes = Vec::new();
+        for other in versions.iter().filter(|other| other.key != candidate.key) {
+            for req in &other.dependents {
+                // Without known requirements, nothing can be suggested
+                let old_req = req.req.clone()?;
+                let new_req = (!old_req.matches(target_version)).then(|| caret_req(target_version));
+                changes.push(ReqChange {
+                    dependent: req.dependent,
+                    current: other.key,
+                    old_req,
+                    new_req,
+                });
+            }
+        }
+        let changed: Vec<&ReqChange> =
+            changes.iter().filter(|change| change.new_req.is_some()).collect();
+        let lowered = changed
+            .iter()
+            .filter(|change| project.packages[change.current].cm_pkg.version > *target_version)
+            .count();
+        let score = (lowered, changed.len() - lowered, changes.len());
+        if best.as_ref().is_none_or(|(best_score, _)| score < *best_score) {
+            best = Some((
+                score,
+                Suggestion {
+                    target: candidate.key,
+                    changes,
+                },
+            ));
+        }
+    }
+    best.map(|(_, suggestion)| suggestion)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{caret_req, find_duplicates, suggest, DuplicateVersion, Requirement},
+        crate::project::Project,
+        cargo_metadata::semver::Version,
+        serde_json::json,
+        std::collections::BTreeMap,
+    };
+
+    #[test]
+    fn smallest_caret_requirements() {
+        let caret = |version: &str| caret_req(&Version::parse(version).unwrap());
+        assert_eq!(caret("1.2.3"), "^1.2");
+        assert_eq!(caret("0.5.1"), "^0.5");
+        assert_eq!(caret("0.0.7"), "^0.0.7");
+    }
+
+    /// A project with a workspace member `app`, which depends on the dependents in `deps`.
+    ///
+    /// Each entry is a dependent, and the name, version and requirement of its dependency.
+    /// Crates with the same name and version are resolved to the same package.
+    fn project(deps: &[(&str, &str, &str, &str)]) -> Project {
+        // Dependencies of each package, as name, version and requirement
+        let mut graph: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for &(dependent, name, version, req) in deps {
+            graph.entry(("app", "0.1.0")).or_default().push((dependent, "1.0.0", "^1"));
+            graph.entry((dependent, "1.0.0")).or_default().push((name, version, req));
+            graph.entry((name, version)).or_default();
+        }
+        let id = |name: &str, version: &str| format!("{name} {version}");
+        let (packages, nodes): (Vec<_>, Vec<_>) = graph
+            .iter()
+            .map(|(&(name, version), deps)| {
+                let package = json!({
+                    "name": name,
+                    "version": version,
+                    "id": id(name, version),
+                    "dependencies": deps.iter().map(|(dep, _, req)| json!({
+                        "name": dep,
+                        "req": req,
+                        "kind": null,
+                        "optional": false,
+                        "uses_default_features": true,
+                        "features": [],
+                        "target": null,
+                    })).collect::<Vec<_>>(),
+                    "targets": [],
+                    "features": {},
+                    "manifest_path": format!("/nonexistent/{name}-{version}/Cargo.toml"),
+                });
+                let node = json!({
+                    "id": id(name, version),
+                    "deps": deps.iter().map(|(dep, ver, _)| json!({
+                        "name": dep,
+                        "pkg": id(dep, ver),
+                        "dep_kinds": [{"kind": null, "target": null}],
+                    })).collect::<Vec<_>>(),
+                    "dependencies":
+                        deps.iter().map(|(dep, ver, _)| id(dep, ver)).collect::<Vec<_>>(),
+                    "features": [],
+                });
+                (package, node)
+            })
+            .unzip();
+        let metadata = serde_json::from_value(json!({
+            "packages": packages,
+            "workspace_members": [id("app", "0.1.0")],
+            "resolve": {"nodes": nodes, "root": id("app", "0.1.0")},
+            "workspace_root": "/nonexistent",
+            "target_directory": "/nonexistent/target",
+            "version": 1,
+        }))
+        .unwrap();
+        Project::from_metadata_blocking(metadata, &crate::Args::default()).unwrap()
+    }
+
+    fn name_version(project: &Project, key: crate::project::PkgKey) -> String {
+        let pkg = &project.packages[key].cm_pkg;
+        format!("{} {}", pkg.name, pkg.version)
+    }
+
+    #[test]
+    fn suggests_raising_requirements_over_lowering() {
+        let project = project(&[
+            ("new", "dup", "1.5.0", "^1.5"),
+            ("older", "dup", "1.0.0", "^1.0"),
+            ("oldest", "dup", "0.9.3", "^0.9"),
+        ]);
+        let duplicates = find_duplicates(&project);
+        assert_eq!(duplicates.len(), 1);
+        let duplicate = &duplicates[0];
+        assert_eq!(duplicate.name, "dup");
+        let suggestion = duplicate.suggestion.as_ref().unwrap();
+        assert_eq!(name_version(&project, suggestion.target), "dup 1.5.0");
+        let changes: Vec<(String, Option<&str>)> = suggestion
+            .changes
+            .iter()
+            .map(|change| {
+                (
+                    name_version(&project, change.dependent),
+                    change.new_req.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("oldest 1.0.0".to_owned(), Some("^1.5")),
+                // Already allows 1.5.0, so only the lockfile has to be updated
+                ("older 1.0.0".to_owned(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_suggestion_without_requirements() {
+        let project = project(&[("a", "dup", "1.0.0", "^1"), ("b", "dup", "2.0.0", "^2")]);
+        let duplicate = &find_duplicates(&project)[0];
+        // As with `--lockfile-only`, where the requirements are unknown
+        let versions: Vec<DuplicateVersion> = duplicate
+            .versions
+            .iter()
+            .map(|version| DuplicateVersion {
+                key: version.key,
+                dependents: version
+                    .dependents
+                    .iter()
+                    .map(|req| Requirement {
+                        dependent: req.dependent,
+                        req: None,
+                    })
+                    .collect(),
+            })
+            .collect();
+        assert!(suggest(&project, &versions).is_none());
+    }
+}
@@ -0,0 +1,321 @@
+//! Writing the dependency graph in formats other tools understand
+
+use {
+    crate::project::{PkgKey, Project},
+    anyhow::{bail, Context},
+    cargo_metadata::DependencyKind,
+    serde::Serialize,
+    std::{
+        collections::{HashMap, HashSet},
+        fmt::Write,
+        io::Write as _,
+    },
+};
+
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// Graphviz DOT
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+    /// JSON with a list of nodes and a list of edges
+    Json,
+}
+
+impl ExportFormat {
+    pub const ALL: [Self; 3] = [Self::Dot, Self::Mermaid, Self::Json];
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Dot => "DOT",
+            Self::Mermaid => "Mermaid",
+            Self::Json => "JSON",
+        }
+    }
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Dot => "dot",
+            Self::Mermaid => "mmd",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Which part of the dependency graph to export
+#[derive(Clone, Copy, PartialEq)]
+pub enum Subgraph {
+    Whole,
+    /// A package and everything that depends on it
+    Ancestors(PkgKey),
+    /// A package and everything it depends on
+    Subtree(PkgKey),
+}
+
+/// Version of the JSON schema. Bumped when the schema changes incompatibly.
+const JSON_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct JsonGraph<'a> {
+    schema_version: u32,
+    /// Id of the root package, if there is one in the graph
+    root: Option<&'a str>,
+    nodes: Vec<Node<'a>>,
+    edges: Vec<Edge<'a>>,
+}
+
+#[derive(Serialize)]
+struct Node<'a> {
+    /// Cargo's package id
+    id: &'a str,
+    name: &'a str,
+    version: String,
+    source: Option<&'a str>,
+    workspace_member: bool,
+}
+
+#[derive(Serialize)]
+struct Edge<'a> {
+    from: &'a str,
+    to: &'a str,
+    /// `normal`, `build` or `dev`. `unknown` for graphs built from the lockfile alone.
+    kind: &'static str,
+    /// Platform the dependency is restricted to, like `cfg(unix)`
+    target: Option<String>,
+    /// Features requested by the dependency declaration, including `default`
+    features: Vec<String>,
+}
+
+fn kind_name(kind: DependencyKind) -> &'static str {
+    match kind {
+        DependencyKind::Build => "build",
+        DependencyKind::Development => "dev",
+        _ => "normal",
+    }
+}
+
+/// The packages of `subgraph`, sorted by name and version
+fn subgraph_nodes(project: &Project, subgraph: Subgraph) -> Vec<PkgKey> {
+    let mut keys: Vec<PkgKey> = match subgraph {
+        Subgraph::Whole => project.packages.keys().collect(),
+        Subgraph::Ancestors(start) | Subgraph::Subtree(start) => {
+            let mut seen = HashSet::from([start]);
+            let mut stack = vec![start];
+            while let Some(key) = stack.pop() {
+                let pkg = &project.packages[key];
+                let links = match subgraph {
+                    Subgraph::Ancestors(_) => &pkg.dependents,
+                    _ => &pkg.dependencies,
+                };
+                for link in links {
+                    if seen.insert(link.pkg_key) {
+                        stack.push(link.pkg_key);
+                    }
+                }
+            }
+            seen.into_iter().collect()
+        }
+    };
+    keys.retain(|key| project.packages[*key].on_target);
+    keys.sort_by(|a, b| {
+        let (a, b) = (&project.packages[*a].cm_pkg, &project.packages[*b].cm_pkg);
+        (&a.name, &a.version, &a.id).cmp(&(&b.name, &b.version, &b.id))
+    });
+    keys
+}
+
+/// The graph as JSON nodes and edges, in a stable order
+fn graph(project: &Project, subgraph: Subgraph) -> JsonGraph<'_> {
+    let keys = subgraph_nodes(project, subgraph);
+    let index: HashMap<PkgKey, usize> = keys.iter().enumerate().map(|(i, key)| (*key, i)).collect();
+    let id = |key: PkgKey| project.packages[key].cm_pkg.id.repr.as_str();
+    let nodes = keys
+        .iter()
+        .map(|&key| {
+            let pkg = &project.packages[key].cm_pkg;
+            Node {
+                id: id(key),
+                name: &pkg.name,
+                version: pkg.version.to_string(),
+                source: pkg.source.as_ref().map(|src| src.repr.as_str()),
+                workspace_member: project.is_workspace_member(key),
+            }
+        })
+        .collect();
+    let mut edges = Vec::new();
+    for &from in &keys {
+        let mut links: Vec<_> = project.packages[from]
+            .dependencies
+            .iter()
+            .filter(|link| index.contains_key(&link.pkg_key) && project.link_on_target(link))
+            .collect();
+        links.sort_by_key(|link| index[&link.pkg_key]);
+        for link in links {
+            if link.kinds.is_empty() {
+                edges.push(Edge {
+                    from: id(from),
+                    to: id(link.pkg_key),
+                    kind: "unknown",
+                    target: None,
+                    features: Vec::new(),
+                });
+            }
+            for kind in &link.kinds {
+                edges.push(Edge {
+                    from: id(from),
+                    to: id(link.pkg_key),
+                    kind: kind_name(kind.kind),
+                    target: kind.target.as_ref().map(ToString::to_string),
+                    features: project.edge_features(from, link.pkg_key, Some(kind)),
+                });
+            }
+        }
+    }
+    JsonGraph {
+        schema_version: JSON_SCHEMA_VERSION,
+        root: project.root.filter(|root| index.contains_key(root)).map(id),
+        nodes,
+        edges,
+    }
+}
+
+/// Export `subgraph` of the dependency graph of `project` in `format`
+pub fn export(project: &Project, subgraph: Subgraph, format: ExportFormat) -> String {
+    let graph = graph(project, subgraph);
+    match format {
+        ExportFormat::Dot => to_dot(&graph),
+        ExportFormat::Mermaid => to_mermaid(&graph),
+        ExportFormat::Json => {
+            let mut out = serde_json::to_string_pretty(&graph).unwrap();
+            out.push('\n');
+            out
+        }
+    }
+}
+
+/// Short description of an edge, for labels
+fn edge_label(edge: &Edge) -> String {
+    let mut parts = Vec::new();
+    if edge.kind != "normal" {
+        parts.push(edge.kind.to_owned());
+    }
+    parts.extend(edge.target.clone());
+    if !edge.features.is_empty() {
+        parts.push(edge.features.join(","));
+    }
+    parts.join("; ")
+}
+
+fn to_dot(graph: &JsonGraph) -> String {
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let ids: HashMap<&str, usize> =
+        graph.nodes.iter().enumerate().map(|(i, node)| (node.id, i)).collect();
+    let mut out = String::from("digraph dependencies {\n    node [shape=box];\n");
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let label = quote(&format!("{} {}", node.name, node.version));
+        let style = if node.workspace_member {
+            ", style=bold"
+        } else {
+            ""
+        };
+        writeln!(out, "    n{i} [label={label}{style}];").unwrap();
+    }
+    for edge in &graph.edges {
+        let style = match edge.kind {
+            "build" => ", style=dashed",
+            "dev" => ", style=dotted",
+            _ => "",
+        };
+        writeln!(
+            out,
+            "    n{} -> n{} [kind={}, target={}, features={}, label={}{style}];",
+            ids[edge.from],
+            ids[edge.to],
+            quote(edge.kind),
+            quote(edge.target.as_deref().unwrap_or("")),
+            quote(&edge.features.join(",")),
+            quote(&edge_label(edge)),
+        )
+        .unwrap();
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn to_mermaid(graph: &JsonGraph) -> String {
+    let quote = |s: &str| format!("\"{}\"", s.replace('"', "#quot;"));
+    let ids: HashMap<&str, usize> =
+        graph.nodes.iter().enumerate().map(|(i, node)| (node.id, i)).collect();
+    let mut out = String::from("flowchart TD\n");
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let label = quote(&format!("{} {}", node.name, node.version));
+        writeln!(out, "    n{i}[{label}]").unwrap();
+    }
+    for edge in &graph.edges {
+        let arrow = match edge.kind {
+            "normal" | "unknown" => "-->",
+            _ => "-.->",
+        };
+        let (from, to) = (ids[edge.from], ids[edge.to]);
+        match edge_label(edge) {
+            label if label.is_empty() => writeln!(out, "    n{from} {arrow} n{to}").unwrap(),
+            label => writeln!(out, "    n{from} {arrow}|{}| n{to}", quote(&label)).unwrap(),
+        }
+    }
+    let members: Vec<String> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| node.workspace_member)
+        .map(|(i, _)| format!("n{i}"))
+        .collect();
+    if !members.is_empty() {
+        writeln!(out, "    classDef member stroke-width:3px").unwrap();
+        writeln!(out, "    class {} member", members.join(",")).unwrap();
+    }
+    out
+}
+
+/// Find a package by `name` or `name@version`
+pub fn find_pkg_spec(project: &Project, spec: &str) -> anyhow::Result<PkgKey> {
+    let (name, version) = match spec.split_once('@') {
+        Some((name, version)) => (name, Some(version)),
+        None => (spec, None),
+    };
+    let matches: Vec<PkgKey> = project
+        .packages
+        .iter()
+        .filter(|(_, pkg)| {
+            pkg.cm_pkg.name == name
+                && version.is_none_or(|version| pkg.cm_pkg.version.to_string() == version)
+        })
+        .map(|(key, _)| key)
+        .collect();
+    match matches.as_slice() {
+        [key] => Ok(*key),
+        [] => bail!("No package matches `{spec}`"),
+        _ => bail!("`{spec}` is ambiguous. Specify the version like `{name}@<version>`"),
+    }
+}
+
+/// Export the graph of the project given on the command line, for `--export`
+pub fn run_cli(args: &crate::Args, format: ExportFormat) -> anyhow::Result<()> {
+    let path = match args.metadata_json.as_ref().or(args.manifest_path.as_ref()) {
+        Some(path) => path.clone(),
+        None => std::env::current_dir()?,
+    };
+    let project = Project::load_blocking(&path, args)?;
+    for warning in &project.warnings {
+        eprintln!("Warning: {warning}");
+    }
+    let subgraph = match (&args.export_ancestors, &args.export_subtree) {
+        (Some(spec), _) => Subgraph::Ancestors(find_pkg_spec(&project, spec)?),
+        (_, Some(spec)) => Subgraph::Subtree(find_pkg_spec(&project, spec)?),
+        _ => Subgraph::Whole,
+    };
+    let out = export(&project, subgraph, format);
+    match &args.export_output {
+        Some(path) => std::fs::write(path, out)
+            .with_context(|| format!("Failed to write {}", path.display()))?,
+        None => std::io::stdout().write_all(out.as_bytes())?,
+    }
+    Ok(())
+}
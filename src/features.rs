@@ -0,0 +1,404 @@
+//! Following what features turn on, across packages
+//!
+//! The values of a `[features]` entry can be other features of the same package (`foo`),
+//! optional dependencies (`dep:foo`), or features of dependencies (`foo/bar`, and the weak
+//! `foo?/bar` which doesn't enable `foo` by itself).
+
+use {
+    crate::project::{Pkg, PkgKey, Project},
+    cargo_metadata::Dependency,
+    std::collections::{HashMap, VecDeque},
+};
+
+/// A parsed value of a `[features]` entry
+#[derive(Debug, PartialEq)]
+pub enum FeatureValue<'a> {
+    /// Another feature of the same package
+    Feature(&'a str),
+    /// `dep:name`, enabling an optional dependency
+    Dep(&'a str),
+    /// `dep/feature`, or the weak `dep?/feature`
+    DepFeature {
+        dep: &'a str,
+        feature: &'a str,
+        weak: bool,
+    },
+}
+
+impl<'a> FeatureValue<'a> {
+    pub fn parse(value: &'a str) -> Self {
+        if let Some(dep) = value.strip_prefix("dep:") {
+            return Self::Dep(dep);
+        }
+        match value.split_once('/') {
+            Some((dep, feature)) => match dep.strip_suffix('?') {
+                Some(dep) => Self::DepFeature {
+                    dep,
+                    feature,
+                    weak: true,
+                },
+                None => Self::DepFeature {
+                    dep,
+                    feature,
+                    weak: false,
+                },
+            },
+            None => Self::Feature(value),
+        }
+    }
+}
+
+/// Something that can be turned on by a feature
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum FeatureNode {
+    /// A feature of a package
+    Feature { pkg: PkgKey, feature: String },
+    /// An optional dependency `name` of `pkg` being enabled, which resolved to `dep_pkg`
+    Dep {
+        pkg: PkgKey,
+        name: String,
+        dep_pkg: PkgKey,
+    },
+    /// A dependency that isn't part of the resolved dependency graph
+    Unresolved { pkg: PkgKey, value: String },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// `feature`, another feature of the same package
+    Feature,
+    /// `dep:name`, or `name/feature` enabling an optional dependency
+    OptionalDep,
+    /// `name/feature`
+    DepFeature,
+    /// `name?/feature`, only applies if the dependency is enabled by something else
+    WeakDepFeature,
+    /// A feature requested by a dependency declaration (including default features)
+    DepDeclaration,
+    /// A non-optional dependency of a package, on because the package itself is depended on.
+    ///
+    /// Only used by [`trace_feature`], as it's not something features turn on.
+    RequiredDep,
+}
+
+pub struct FeatureEdge {
+    pub kind: EdgeKind,
+    pub to: FeatureNode,
+}
+
+/// The declarations of dependency `name` of `pkg`, and the package they resolved to
+fn resolve_dep_name<'p>(
+    pkg: &'p Pkg,
+    name: &str,
+    project: &Project,
+) -> (Vec<&'p Dependency>, Option<PkgKey>) {
+    let decls: Vec<&Dependency> = pkg
+        .cm_pkg
+        .dependencies
+        .iter()
+        .filter(|dep| dep.rename.as_deref().unwrap_or(&dep.name) == name)
+        .collect();
+    let key = decls
+        .iter()
+        .find_map(|dep| pkg.resolved_dep(dep, &project.packages))
+        .map(|dep_pkg| dep_pkg.key);
+    (decls, key)
+}
+
+/// Everything `node` turns on directly
+pub fn feature_edges(project: &Project, node: &FeatureNode) -> Vec<FeatureEdge> {
+    let mut edges = Vec::new();
+    match node {
+        FeatureNode::Feature { pkg, feature } => {
+            let pkg = &project.packages[*pkg];
+            let Some(values) = pkg.cm_pkg.features.get(feature) else {
+                return edges;
+            };
+            for value in values {
+                let unresolved = || FeatureNode::Unresolved {
+                    pkg: pkg.key,
+                    value: value.clone(),
+                };
+                match FeatureValue::parse(value) {
+                    FeatureValue::Feature(feature) => edges.push(FeatureEdge {
+                        kind: EdgeKind::Feature,
+                        to: FeatureNode::Feature {
+                            pkg: pkg.key,
+                            feature: feature.to_owned(),
+                        },
+                    }),
+                    FeatureValue::Dep(name) => {
+                        let to = match resolve_dep_name(pkg, name, project) {
+                            (_, Some(dep_pkg)) => FeatureNode::Dep {
+                                pkg: pkg.key,
+                                name: name.to_owned(),
+                                dep_pkg,
+                            },
+                            (_, None) => unresolved(),
+                        };
+                        edges.push(FeatureEdge {
+                            kind: EdgeKind::OptionalDep,
+                            to,
+                        });
+                    }
+                    FeatureValue::DepFeature { dep, feature, weak } => {
+                        let (decls, dep_pkg) = resolve_dep_name(pkg, dep, project);
+                        let Some(dep_pkg) = dep_pkg else {
+                            edges.push(FeatureEdge {
+                                kind: if weak {
+                                    EdgeKind::WeakDepFeature
+                                } else {
+                                    EdgeKind::DepFeature
+                                },
+                                to: unresolved(),
+                            });
+                            continue;
+                        };
+                        if !weak && decls.iter().any(|dep| dep.optional) {
+                            edges.push(FeatureEdge {
+                                kind: EdgeKind::OptionalDep,
+                                to: FeatureNode::Dep {
+                                    pkg: pkg.key,
+                                    name: dep.to_owned(),
+                                    dep_pkg,
+                                },
+                            });
+                        }
+                        edges.push(FeatureEdge {
+                            kind: if weak {
+                                EdgeKind::WeakDepFeature
+                            } else {
+                                EdgeKind::DepFeature
+                            },
+                            to: FeatureNode::Feature {
+                                pkg: dep_pkg,
+                                feature: feature.to_owned(),
+                            },
+                        });
+                    }
+                }
+            }
+        }
+        FeatureNode::Dep { pkg, name, dep_pkg } => {
+            let (decls, _) = resolve_dep_name(&project.packages[*pkg], name, project);
+            let dep_features = &project.packages[*dep_pkg].cm_pkg.features;
+            let mut features: Vec<&str> = Vec::new();
+            if decls.iter().any(|dep| dep.uses_default_features)
+                && dep_features.contains_key("default")
+            {
+                features.push("default");
+            }
+            for dep in &decls {
+                for feature in &dep.features {
+                    if !features.contains(&feature.as_str()) {
+                        features.push(feature);
+                    }
+                }
+            }
+            edges.extend(features.into_iter().map(|feature| FeatureEdge {
+                kind: EdgeKind::DepDeclaration,
+                to: FeatureNode::Feature {
+                    pkg: *dep_pkg,
+                    feature: feature.to_owned(),
+                },
+            }));
+        }
+        FeatureNode::Unresolved { .. } => {}
+    }
+    edges
+}
+
+/// Something that turned a feature node on, see [`feature_sources`]
+pub struct FeatureSource {
+    /// The kind of the edge from `from` to the node
+    pub kind: EdgeKind,
+    pub from: FeatureNode,
+}
+
+/// Why a chain of feature nodes starts where it does, see [`trace_feature`]
+pub enum ChainStart {
+    /// Default features of a workspace member
+    MemberDefault,
+    /// A feature of a workspace member, selected with `--features` or `--all-features`
+    MemberSelected,
+    /// A non-optional dependency declaration of a workspace member
+    Declaration,
+    /// Nothing enabled could be found that turns this on
+    Unknown,
+}
+
+/// Whether `node` is turned on in the resolved project
+fn is_active(project: &Project, node: &FeatureNode) -> bool {
+    match node {
+        FeatureNode::Feature { pkg, feature } => {
+            project.packages[*pkg].enabled_features.contains(feature)
+        }
+        FeatureNode::Dep { pkg, name, .. } => {
+            let (decls, _) = resolve_dep_name(&project.packages[*pkg], name, project);
+            decls.iter().any(|dep| !dep.optional) || !feature_sources(project, node).is_empty()
+        }
+        FeatureNode::Unresolved { .. } => false,
+    }
+}
+
+/// The nodes that are turned on, and directly turn on `node`
+pub fn feature_sources(project: &Project, node: &FeatureNode) -> Vec<FeatureSource> {
+    let target_pkg = match node {
+        FeatureNode::Feature { pkg, .. } => &project.packages[*pkg],
+        // Optional dependencies are only turned on by features of the same package
+        FeatureNode::Dep { pkg, .. } => {
+            let pkg = &project.packages[*pkg];
+            return active_feature_nodes(pkg)
+                .flat_map(|from| edges_to(project, from, node))
+                .collect();
+        }
+        FeatureNode::Unresolved { .. } => return Vec::new(),
+    };
+    let mut candidates: Vec<FeatureNode> = active_feature_nodes(target_pkg).collect();
+    for dependent in dependents(target_pkg) {
+        candidates.extend(active_feature_nodes(&project.packages[dependent]));
+    }
+    candidates.extend(active_declarations_of(project, target_pkg));
+    candidates.into_iter().flat_map(|from| edges_to(project, from, node)).collect()
+}
+
+/// The packages depending on `pkg`, each once
+fn dependents(pkg: &Pkg) -> Vec<PkgKey> {
+    let mut dependents: Vec<PkgKey> = Vec::new();
+    for link in &pkg.dependents {
+        if !dependents.contains(&link.pkg_key) {
+            dependents.push(link.pkg_key);
+        }
+    }
+    dependents
+}
+
+/// The dependency declarations that resolved to `pkg` and are turned on
+fn active_declarations_of(project: &Project, pkg: &Pkg) -> Vec<FeatureNode> {
+    let mut nodes = Vec::new();
+    for dependent in dependents(pkg) {
+        let dependent = &project.packages[dependent];
+        for dep in &dependent.cm_pkg.dependencies {
+            if dependent.resolved_dep(dep, &project.packages).map(|pkg| pkg.key) != Some(pkg.key) {
+                continue;
+            }
+            let dep_node = FeatureNode::Dep {
+                pkg: dependent.key,
+                name: dep.rename.clone().unwrap_or_else(|| dep.name.clone()),
+                dep_pkg: pkg.key,
+            };
+            if !nodes.contains(&dep_node) && is_active(project, &dep_node) {
+                nodes.push(dep_node);
+            }
+        }
+    }
+    nodes
+}
+
+fn active_feature_nodes(pkg: &Pkg) -> impl Iterator<Item = FeatureNode> + '_ {
+    pkg.enabled_features.iter().map(|feature| FeatureNode::Feature {
+        pkg: pkg.key,
+        feature: feature.clone(),
+    })
+}
+
+/// The edges from `from` to `to`, as sources of `to`
+fn edges_to(project: &Project, from: FeatureNode, to: &FeatureNode) -> Vec<FeatureSource> {
+    feature_edges(project, &from)
+        .into_iter()
+        .filter(|edge| edge.to == *to)
+        .map(|edge| FeatureSource {
+            kind: edge.kind,
+            from: from.clone(),
+        })
+        .collect()
+}
+
+/// Follow the sources of `node` back to where they start, taking the shortest way.
+///
+/// Non-optional dependencies of packages other than workspace members are followed further,
+/// to the declarations that pulled in the package, so the chain ends at a workspace member.
+///
+/// Returns the start, and the chain of nodes from the start up to (not including) `node`,
+/// each with the kind of edge to the next node.
+pub fn trace_feature(project: &Project, node: &FeatureNode) -> (ChainStart, Vec<FeatureSource>) {
+    // Breadth first search backwards. `next` maps each visited node to the node it turns on.
+    let mut next: HashMap<FeatureNode, (FeatureNode, EdgeKind)> = HashMap::new();
+    let mut queue = VecDeque::from([node.clone()]);
+    while let Some(current) = queue.pop_front() {
+        let mut sources = feature_sources(project, &current);
+        if let FeatureNode::Dep { pkg, .. } = &current {
+            if sources.is_empty() && !project.is_workspace_member(*pkg) {
+                sources = active_declarations_of(project, &project.packages[*pkg])
+                    .into_iter()
+                    .map(|from| FeatureSource {
+                        kind: EdgeKind::RequiredDep,
+                        from,
+                    })
+                    .collect();
+            }
+        }
+        if sources.is_empty() && current != *node {
+            let start = chain_start(project, &current);
+            let mut chain = Vec::new();
+            let mut cursor = current;
+            while let Some((to, kind)) = next.get(&cursor) {
+                chain.push(FeatureSource {
+                    kind: *kind,
+                    from: cursor.clone(),
+                });
+                cursor = to.clone();
+            }
+            return (start, chain);
+        }
+        for source in sources {
+            if source.from == *node || next.contains_key(&source.from) {
+                continue;
+            }
+            next.insert(source.from.clone(), (current.clone(), source.kind));
+            queue.push_back(source.from);
+        }
+    }
+    (chain_start(project, node), Vec::new())
+}
+
+fn chain_start(project: &Project, node: &FeatureNode) -> ChainStart {
+    match node {
+        FeatureNode::Feature { pkg, feature } if project.is_workspace_member(*pkg) => {
+            if feature == "default" {
+                ChainStart::MemberDefault
+            } else {
+                ChainStart::MemberSelected
+            }
+        }
+        FeatureNode::Dep { .. } => ChainStart::Declaration,
+        _ => ChainStart::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FeatureValue;
+
+    #[test]
+    fn parse_feature_values() {
+        assert_eq!(FeatureValue::parse("std"), FeatureValue::Feature("std"));
+        assert_eq!(FeatureValue::parse("dep:serde"), FeatureValue::Dep("serde"));
+        assert_eq!(
+            FeatureValue::parse("serde/derive"),
+            FeatureValue::DepFeature {
+                dep: "serde",
+                feature: "derive",
+                weak: false,
+            }
+        );
+        assert_eq!(
+            FeatureValue::parse("serde?/derive"),
+            FeatureValue::DepFeature {
+                dep: "serde",
+                feature: "derive",
+                weak: true,
+            }
+        );
+    }
+}
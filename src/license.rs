@@ -0,0 +1,583 @@
+//! SPDX license expressions, and checking packages against a license policy
+//!
+//! Expressions like `MIT OR Apache-2.0` are parsed and normalized, so that equivalent
+//! expressions written differently (`Apache-2.0 OR MIT`, or the legacy `MIT/Apache-2.0`)
+//! end up the same.
+
+use {
+    crate::{license_text::LicenseFiles, notices::PkgDir, project::PkgKey},
+    anyhow::Context,
+    cargo_metadata::{camino::Utf8PathBuf, Package},
+    std::{
+        collections::{BTreeMap, HashMap},
+        fmt,
+        path::Path,
+    },
+};
+
+/// Name of the policy file that is looked for in the workspace root
+pub const POLICY_FILENAME: &str = "license-policy.toml";
+
+/// Commonly used SPDX identifiers, for fixing up their capitalization
+const KNOWN_IDS: &[&str] = &[
+    "0BSD",
+    "AGPL-3.0-only",
+    "AGPL-3.0-or-later",
+    "Apache-2.0",
+    "BlueOak-1.0.0",
+    "BSD-1-Clause",
+    "BSD-2-Clause",
+    "BSD-3-Clause",
+    "BSL-1.0",
+    "CC0-1.0",
+    "CDLA-Permissive-2.0",
+    "GPL-2.0-only",
+    "GPL-2.0-or-later",
+    "GPL-3.0-only",
+    "GPL-3.0-or-later",
+    "ISC",
+    "LGPL-2.0-only",
+    "LGPL-2.0-or-later",
+    "LGPL-2.1-only",
+    "LGPL-2.1-or-later",
+    "LGPL-3.0-only",
+    "LGPL-3.0-or-later",
+    "MIT",
+    "MIT-0",
+    "MPL-2.0",
+    "NCSA",
+    "OpenSSL",
+    "Unicode-3.0",
+    "Unicode-DFS-2016",
+    "Unlicense",
+    "WTFPL",
+    "Zlib",
+];
+
+/// Deprecated GNU license identifiers, which mean the `-only` variant,
+/// or the `-or-later` one with a `+`
+const DEPRECATED_GNU_IDS: &[&str] = &[
+    "AGPL-3.0", "GPL-2.0", "GPL-3.0", "LGPL-2.0", "LGPL-2.1", "LGPL-3.0",
+];
+
+/// A parsed SPDX license expression
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LicenseExpr {
+    /// A single license, optionally with an exception (`Apache-2.0 WITH LLVM-exception`)
+    License {
+        id: String,
+        exception: Option<String>,
+    },
+    /// All of the licenses apply
+    And(Vec<LicenseExpr>),
+    /// Any of the licenses can be chosen
+    Or(Vec<LicenseExpr>),
+}
+
+enum Token<'a> {
+    Open,
+    Close,
+    Word(&'a str),
+}
+
+fn tokenize(src: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (i, ch) in src.char_indices() {
+        let separator = match ch {
+            '(' => Some(Token::Open),
+            ')' => Some(Token::Close),
+            // Legacy `MIT/Apache-2.0` syntax
+            '/' => Some(Token::Word("OR")),
+            _ if ch.is_whitespace() => None,
+            _ => {
+                start.get_or_insert(i);
+                continue;
+            }
+        };
+        if let Some(start) = start.take() {
+            tokens.push(Token::Word(&src[start..i]));
+        }
+        tokens.extend(separator);
+    }
+    if let Some(start) = start {
+        tokens.push(Token::Word(&src[start..]));
+    }
+    tokens
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek_word(&self, word: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some(Token::Word(w)) if w.eq_ignore_ascii_case(word))
+    }
+    fn or_expr(&mut self) -> Result<LicenseExpr, String> {
+        let mut operands = vec![self.and_expr()?];
+        while self.peek_word("OR") {
+            self.pos += 1;
+            operands.push(self.and_expr()?);
+        }
+        Ok(match operands.len() {
+            1 => operands.remove(0),
+            _ => LicenseExpr::Or(operands),
+        })
+    }
+    fn and_expr(&mut self) -> Result<LicenseExpr, String> {
+        let mut operands = vec![self.primary()?];
+        while self.peek_word("AND") {
+            self.pos += 1;
+            operands.push(self.primary()?);
+        }
+        Ok(match operands.len() {
+            1 => operands.remove(0),
+            _ => LicenseExpr::And(operands),
+        })
+    }
+    fn primary(&mut self) -> Result<LicenseExpr, String> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        match token {
+            Some(Token::Open) => {
+                let expr = self.or_expr()?;
+                match self.tokens.get(self.pos) {
+                    Some(Token::Close) => {
+                        self.pos += 1;
+                        Ok(expr)
+                    }
+                    _ => Err("Missing closing parenthesis".into()),
+                }
+            }
+            Some(Token::Word(word)) if is_operator(word) => {
+                Err(format!("Expected a license, found `{word}`"))
+            }
+            Some(Token::Word(id)) => {
+                let exception = if self.peek_word("WITH") {
+                    self.pos += 1;
+                    match self.tokens.get(self.pos) {
+                        Some(Token::Word(exception)) if !is_operator(exception) => {
+                            self.pos += 1;
+                            Some((*exception).to_owned())
+                        }
+                        _ => return Err("Expected an exception after `WITH`".into()),
+                    }
+                } else {
+                    None
+                };
+                Ok(LicenseExpr::License {
+                    id: canonical_id(id),
+                    exception,
+                })
+            }
+            Some(Token::Close) => Err("Unexpected closing parenthesis".into()),
+            None => Err("Unexpected end of expression".into()),
+        }
+    }
+}
+
+fn is_operator(word: &str) -> bool {
+    ["AND", "OR", "WITH"].iter().any(|op| word.eq_ignore_ascii_case(op))
+}
+
+/// The identifier with the capitalization SPDX uses, if it's a known one.
+///
+/// Deprecated GNU identifiers (`GPL-3.0`, `LGPL-2.1+`) are replaced with the current ones.
+fn canonical_id(id: &str) -> String {
+    let (base, or_later) = match id.strip_suffix('+') {
+        Some(base) => (base, true),
+        None => (id, false),
+    };
+    let id = match DEPRECATED_GNU_IDS.iter().find(|gnu| gnu.eq_ignore_ascii_case(base)) {
+        Some(gnu) if or_later => format!("{gnu}-or-later"),
+        Some(gnu) => format!("{gnu}-only"),
+        None => id.to_owned(),
+    };
+    KNOWN_IDS
+        .iter()
+        .find(|known| known.eq_ignore_ascii_case(&id))
+        .map_or(id, |known| (*known).to_owned())
+}
+
+impl LicenseExpr {
+    /// Parse and normalize an expression
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            tokens: tokenize(src),
+            pos: 0,
+        };
+        let expr = parser.or_expr()?;
+        if parser.pos < parser.tokens.len() {
+            return Err("Unexpected input after the expression".into());
+        }
+        Ok(expr.normalize())
+    }
+    /// Flatten nested operators of the same kind, and sort and deduplicate their operands
+    fn normalize(self) -> Self {
+        fn normalize_operands(
+            operands: Vec<LicenseExpr>,
+            flatten: impl Fn(LicenseExpr) -> Result<Vec<LicenseExpr>, LicenseExpr>,
+        ) -> Vec<LicenseExpr> {
+            let mut flat = Vec::new();
+            for operand in operands {
+                match flatten(operand.normalize()) {
+                    Ok(inner) => flat.extend(inner),
+                    Err(operand) => flat.push(operand),
+                }
+            }
+            flat.sort();
+            flat.dedup();
+            flat
+        }
+        let wrap = |mut operands: Vec<Self>, op: fn(Vec<Self>) -> Self| match operands.len() {
+            1 => operands.remove(0),
+            _ => op(operands),
+        };
+        match self {
+            Self::And(operands) => wrap(
+                normalize_operands(operands, |expr| match expr {
+                    Self::And(inner) => Ok(inner),
+                    other => Err(other),
+                }),
+                Self::And,
+            ),
+            Self::Or(operands) => wrap(
+                normalize_operands(operands, |expr| match expr {
+                    Self::Or(inner) => Ok(inner),
+                    other => Err(other),
+                }),
+                Self::Or,
+            ),
+            license => license,
+        }
+    }
+    /// The license identifiers in this expression, sorted and without duplicates
+    pub fn identifiers(&self) -> Vec<&str> {
+        let mut ids = Vec::new();
+        self.collect_identifiers(&mut ids);
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+    fn collect_identifiers<'a>(&'a self, ids: &mut Vec<&'a str>) {
+        match self {
+            Self::License { id, .. } => ids.push(id),
+            Self::And(operands) | Self::Or(operands) => {
+                for operand in operands {
+                    operand.collect_identifiers(ids);
+                }
+            }
+        }
+    }
+    /// The status of the expression, given the status of each license in it
+    fn evaluate(&self, status: &impl Fn(&str, Option<&str>) -> LicenseStatus) -> LicenseStatus {
+        let operand_statuses = |operands: &[Self]| {
+            operands.iter().map(|operand| operand.evaluate(status)).collect::<Vec<_>>()
+        };
+        match self {
+            Self::License { id, exception } => status(id, exception.as_deref()),
+            // Any of the licenses can be chosen, so the best one counts
+            Self::Or(operands) => operand_statuses(operands).into_iter().min().unwrap(),
+            // All of them apply, so the worst one counts
+            Self::And(operands) => operand_statuses(operands).into_iter().max().unwrap(),
+        }
+    }
+}
+
+impl fmt::Display for LicenseExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let write_operands = |f: &mut fmt::Formatter<'_>, operands: &[Self], op: &str| {
+            for (i, operand) in operands.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {op} ")?;
+                }
+                match operand {
+                    Self::License { .. } => write!(f, "{operand}")?,
+                    _ => write!(f, "({operand})")?,
+                }
+            }
+            Ok(())
+        };
+        match self {
+            Self::License {
+                id,
+                exception: None,
+            } => f.write_str(id),
+            Self::License {
+                id,
+                exception: Some(exception),
+            } => write!(f, "{id} WITH {exception}"),
+            Self::And(operands) => write_operands(f, operands, "AND"),
+            Self::Or(operands) => write_operands(f, operands, "OR"),
+        }
+    }
+}
+
+/// The license information of a package
+pub enum PkgLicense {
+    Expr(LicenseExpr),
+    /// A `license` that couldn't be parsed, and why
+    Invalid {
+        license: String,
+        error: String,
+    },
+    /// Only a `license_file` is given
+    File(Utf8PathBuf),
+    Missing,
+}
+
+impl PkgLicense {
+    pub fn of(pkg: &Package) -> Self {
+        match (&pkg.license, &pkg.license_file) {
+            (Some(license), _) => match LicenseExpr::parse(license) {
+                Ok(expr) => Self::Expr(expr),
+                Err(error) => Self::Invalid {
+                    license: license.clone(),
+                    error,
+                },
+            },
+            (None, Some(file)) => Self::File(file.clone()),
+            (None, None) => Self::Missing,
+        }
+    }
+    /// The name of the group of packages with this license
+    pub fn group_name(&self) -> String {
+        match self {
+            Self::Expr(expr) => expr.to_string(),
+            Self::Invalid { license, .. } => license.clone(),
+            Self::File(_) => "<license file>".into(),
+            Self::Missing => "<no license>".into(),
+        }
+    }
+}
+
+/// How a package's license relates to the policy, from best to worst
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LicenseStatus {
+    Compliant,
+    /// Only licenses that have to be looked at by someone
+    NeedsReview,
+    /// The license isn't known, or the policy doesn't say anything about it
+    Unknown,
+    Violating,
+}
+
+impl LicenseStatus {
+    pub const ALL: [Self; 4] = [
+        Self::Compliant,
+        Self::NeedsReview,
+        Self::Unknown,
+        Self::Violating,
+    ];
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Compliant => "compliant",
+            Self::NeedsReview => "needs review",
+            Self::Unknown => "unknown",
+            Self::Violating => "violating",
+        }
+    }
+}
+
+/// Which licenses are acceptable for a project.
+///
+/// Licenses are given as SPDX identifiers, optionally with an exception
+/// (`Apache-2.0 WITH LLVM-exception`), and matched case-insensitively.
+#[derive(serde::Deserialize, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LicensePolicy {
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+    /// Licenses that are acceptable only after someone has looked at them
+    pub review: Vec<String>,
+    /// Additional allowed licenses for particular crates, by `name` or `name@version`.
+    /// They take precedence over `deny`.
+    ///
+    /// Crates without a license expression (e.g. only a `license_file`)
+    /// are compliant if they have an exception.
+    pub exceptions: BTreeMap<String, Vec<String>>,
+}
+
+impl LicensePolicy {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&data).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+    /// The exceptions that apply to `pkg`
+    fn exceptions_for(&self, pkg: &Package) -> Vec<&String> {
+        let versioned = format!("{}@{}", pkg.name, pkg.version);
+        self.exceptions
+            .iter()
+            .filter(|(spec, _)| **spec == pkg.name || **spec == versioned)
+            .flat_map(|(_, licenses)| licenses)
+            .collect()
+    }
+    pub fn check(&self, pkg: &Package, license: &PkgLicense) -> LicenseStatus {
+        let PkgLicense::Expr(expr) = license else {
+            return if !self.exceptions_for(pkg).is_empty() {
+                LicenseStatus::Compliant
+            } else {
+                LicenseStatus::Unknown
+            };
+        };
+        // Policy entries may use deprecated identifiers too
+        let matches = |list: &[String], id: &str, exception: Option<&str>| {
+            list.iter().any(|entry| {
+                let (entry_id, entry_exception) = match entry.split_once(" WITH ") {
+                    Some((entry_id, entry_exception)) => (entry_id, Some(entry_exception.trim())),
+                    None => (entry.as_str(), None),
+                };
+                canonical_id(entry_id.trim()).eq_ignore_ascii_case(id)
+                    && entry_exception.is_none_or(|entry_exception| {
+                        exception.is_some_and(|exception| {
+                            entry_exception.eq_ignore_ascii_case(exception)
+                        })
+                    })
+            })
+        };
+        let pkg_exceptions: Vec<String> = self.exceptions_for(pkg).into_iter().cloned().collect();
+        // Exceptions for a crate override the global lists
+        expr.evaluate(&|id, exception| {
+            if matches(&pkg_exceptions, id, exception) {
+                LicenseStatus::Compliant
+            } else if matches(&self.deny, id, exception) {
+                LicenseStatus::Violating
+            } else if matches(&self.allow, id, exception) {
+                LicenseStatus::Compliant
+            } else if matches(&self.review, id, exception) {
+                LicenseStatus::NeedsReview
+            } else {
+                LicenseStatus::Unknown
+            }
+        })
+    }
+}
+
+/// The licenses of all packages of a project, grouped in different ways
+pub struct LicenseReport {
+    pub licenses: HashMap<PkgKey, PkgLicense>,
+    /// Status of each package, if there is a policy
+    pub statuses: HashMap<PkgKey, LicenseStatus>,
+    /// Packages by normalized license expression, sorted by expression
+    pub by_expression: Vec<(String, Vec<PkgKey>)>,
+    /// Packages by each license identifier in their expression, sorted by identifier
+    pub by_identifier: Vec<(String, Vec<PkgKey>)>,
+    /// The license files of each package, and whether they match the declared license
+    pub files: HashMap<PkgKey, LicenseFiles>,
+}
+
+impl LicenseReport {
+    /// Check the licenses and license files of `packages`, which takes a while
+    pub fn new(packages: &[PkgDir], policy: Option<&LicensePolicy>) -> Self {
+        let mut packages: Vec<&PkgDir> = packages.iter().collect();
+        // Sorted up front, so the packages of each group come out sorted too
+        packages.sort_by(|a, b| {
+            (&a.cm_pkg.name, &a.cm_pkg.version).cmp(&(&b.cm_pkg.name, &b.cm_pkg.version))
+        });
+        let mut licenses = HashMap::new();
+        let mut statuses = HashMap::new();
+        let mut by_expression: BTreeMap<String, Vec<PkgKey>> = BTreeMap::new();
+        let mut by_identifier: BTreeMap<String, Vec<PkgKey>> = BTreeMap::new();
+        let mut files = HashMap::new();
+        for pkg in packages {
+            let key = pkg.key;
+            files.insert(key, LicenseFiles::check(pkg));
+            let license = PkgLicense::of(&pkg.cm_pkg);
+            by_expression.entry(license.group_name()).or_default().push(key);
+            match &license {
+                PkgLicense::Expr(expr) => {
+                    for id in expr.identifiers() {
+                        by_identifier.entry(id.to_owned()).or_default().push(key);
+                    }
+                }
+                other => by_identifier.entry(other.group_name()).or_default().push(key),
+            }
+            if let Some(policy) = policy {
+                statuses.insert(key, policy.check(&pkg.cm_pkg, &license));
+            }
+            licenses.insert(key, license);
+        }
+        Self {
+            licenses,
+            statuses,
+            by_expression: by_expression.into_iter().collect(),
+            by_identifier: by_identifier.into_iter().collect(),
+            files,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LicenseExpr;
+
+    fn parse(src: &str) -> String {
+        LicenseExpr::parse(src).unwrap().to_string()
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            parse("MIT OR Apache-2.0 AND BSD-3-Clause"),
+            "MIT OR (Apache-2.0 AND BSD-3-Clause)"
+        );
+        assert_eq!(
+            parse("(MIT OR Apache-2.0) AND BSD-3-Clause"),
+            "BSD-3-Clause AND (Apache-2.0 OR MIT)"
+        );
+    }
+
+    #[test]
+    fn nested_operators_are_flattened() {
+        assert_eq!(parse("(MIT OR Apache-2.0) OR MIT"), "Apache-2.0 OR MIT");
+    }
+
+    #[test]
+    fn legacy_slash_means_or() {
+        assert_eq!(parse("MIT/Apache-2.0"), "Apache-2.0 OR MIT");
+        assert_eq!(
+            parse("MIT / Apache-2.0 AND Zlib"),
+            "MIT OR (Apache-2.0 AND Zlib)"
+        );
+    }
+
+    #[test]
+    fn with_binds_to_the_license() {
+        assert_eq!(
+            parse("Apache-2.0 WITH LLVM-exception OR MIT"),
+            "Apache-2.0 WITH LLVM-exception OR MIT"
+        );
+        assert_eq!(
+            LicenseExpr::parse("GPL-2.0-only WITH Classpath-exception-2.0").unwrap(),
+            LicenseExpr::License {
+                id: "GPL-2.0-only".into(),
+                exception: Some("Classpath-exception-2.0".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn ids_are_canonicalized() {
+        assert_eq!(parse("mit or apache-2.0"), "Apache-2.0 OR MIT");
+        assert_eq!(parse("GPL-3.0"), "GPL-3.0-only");
+        assert_eq!(parse("LGPL-2.1+"), "LGPL-2.1-or-later");
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        for src in [
+            "",
+            "MIT OR",
+            "(MIT",
+            "MIT)",
+            "MIT WITH",
+            "AND MIT",
+            "MIT Apache-2.0",
+        ] {
+            assert!(LicenseExpr::parse(src).is_err(), "{src:?} should not parse");
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Recognizing license files, by comparing them against a bundled corpus of SPDX license texts
+//!
+//! Texts are compared by their word pairs, so that line wrapping, punctuation and copyright lines
+//! don't matter. A file with several licenses one after another is recognized too.
+
+use {
+    crate::{
+        license::PkgLicense,
+        notices::{is_copyright_line, license_files, PkgDir},
+    },
+    std::{
+        collections::HashSet,
+        fmt,
+        hash::{DefaultHasher, Hash, Hasher},
+        sync::OnceLock,
+    },
+};
+
+/// License texts, by SPDX identifier. The GPL family texts are the same for the `-only` and
+/// `-or-later` variants, so they go by the bare name. Apache-2.0 is also recognized by the
+/// notice it asks to put in each file, which some crates ship instead of the whole license.
+const CORPUS: &[(&str, &str)] = &[
+    ("0BSD", include_str!("../assets/licenses/0BSD.txt")),
+    (
+        "Apache-2.0",
+        include_str!("../assets/licenses/Apache-2.0.txt"),
+    ),
+    (
+        "Apache-2.0",
+        include_str!("../assets/licenses/Apache-2.0_header.txt"),
+    ),
+    (
+        "Apache-2.0 WITH LLVM-exception",
+        include_str!("../assets/licenses/Apache-2.0_WITH_LLVM-exception.txt"),
+    ),
+    (
+        "BSD-2-Clause",
+        include_str!("../assets/licenses/BSD-2-Clause.txt"),
+    ),
+    (
+        "BSD-3-Clause",
+        include_str!("../assets/licenses/BSD-3-Clause.txt"),
+    ),
+    ("BSL-1.0", include_str!("../assets/licenses/BSL-1.0.txt")),
+    ("CC0-1.0", include_str!("../assets/licenses/CC0-1.0.txt")),
+    ("GPL-2.0", include_str!("../assets/licenses/GPL-2.0.txt")),
+    ("GPL-3.0", include_str!("../assets/licenses/GPL-3.0.txt")),
+    ("ISC", include_str!("../assets/licenses/ISC.txt")),
+    ("LGPL-2.1", include_str!("../assets/licenses/LGPL-2.1.txt")),
+    ("LGPL-3.0", include_str!("../assets/licenses/LGPL-3.0.txt")),
+    ("MIT", include_str!("../assets/licenses/MIT.txt")),
+    ("MPL-2.0", include_str!("../assets/licenses/MPL-2.0.txt")),
+    (
+        "Unicode-3.0",
+        include_str!("../assets/licenses/Unicode-3.0.txt"),
+    ),
+    (
+        "Unlicense",
+        include_str!("../assets/licenses/Unlicense.txt"),
+    ),
+    ("Zlib", include_str!("../assets/licenses/Zlib.txt")),
+];
+
+/// How similar a text has to be to a license text to be recognized as it
+const MATCH_THRESHOLD: f32 = 0.8;
+/// How much of a license text has to be in a file with several licenses for it to count
+const CONTAINED_THRESHOLD: f32 = 0.9;
+
+type WordPairs = HashSet<u64>;
+
+/// Hashes of each pair of consecutive words, leaving out copyright lines
+fn word_pairs(text: &str) -> WordPairs {
+    let words: Vec<String> = text
+        .lines()
+        .filter(|line| !is_copyright_line(line))
+        .flat_map(|line| line.split(|ch: char| !ch.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect();
+    words
+        .windows(2)
+        .map(|pair| {
+            let mut hasher = DefaultHasher::new();
+            pair.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+fn corpus() -> &'static [(&'static str, WordPairs)] {
+    static CORPUS_PAIRS: OnceLock<Vec<(&str, WordPairs)>> = OnceLock::new();
+    CORPUS_PAIRS.get_or_init(|| CORPUS.iter().map(|(id, text)| (*id, word_pairs(text))).collect())
+}
+
+/// A recognized license file
+pub struct TextMatch {
+    /// The licenses in the file, usually just one
+    pub ids: Vec<&'static str>,
+    /// How well the file matches, from 0 to 1
+    pub similarity: f32,
+}
+
+/// Find the license(s) in `text`
+pub fn identify(text: &str) -> Option<TextMatch> {
+    let pairs = word_pairs(text);
+    if pairs.is_empty() {
+        return None;
+    }
+    let shared = |license: &WordPairs| pairs.intersection(license).count() as f32;
+    let (best_id, best) = corpus()
+        .iter()
+        .map(|(id, license)| {
+            (
+                *id,
+                2.0 * shared(license) / (pairs.len() + license.len()) as f32,
+            )
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    if best >= MATCH_THRESHOLD {
+        return Some(TextMatch {
+            ids: vec![best_id],
+            similarity: best,
+        });
+    }
+    // Several licenses in one file. Bigger licenses go first, so the ones that are
+    // mostly part of an already found license can be skipped.
+    let mut contained: Vec<&(&str, WordPairs)> = corpus()
+        .iter()
+        .filter(|(_, license)| shared(license) / license.len() as f32 >= CONTAINED_THRESHOLD)
+        .collect();
+    contained.sort_by_key(|(_, license)| std::cmp::Reverse(license.len()));
+    let mut ids = Vec::new();
+    let mut covered = WordPairs::new();
+    for (id, license) in contained {
+        let new = license.difference(&covered).count() as f32 / license.len() as f32;
+        if new < 1.0 - CONTAINED_THRESHOLD {
+            continue;
+        }
+        covered.extend(license.intersection(&pairs));
+        ids.push(*id);
+    }
+    let similarity = covered.len() as f32 / pairs.len() as f32;
+    (!ids.is_empty() && similarity >= MATCH_THRESHOLD).then_some(TextMatch { ids, similarity })
+}
+
+/// A license file of a package, and what it was recognized as
+pub struct LicenseFile {
+    /// Path relative to the package directory
+    pub name: String,
+    pub recognized: Option<TextMatch>,
+}
+
+/// How the license files of a package disagree with its declared license
+pub enum Mismatch {
+    NoFile,
+    /// The files contain licenses that aren't in the declared expression
+    Undeclared {
+        declared: String,
+        found: Vec<&'static str>,
+    },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoFile => f.write_str("no license file present"),
+            Self::Undeclared { declared, found } => {
+                write!(f, "declares {declared}, ships {} text", found.join(" and "))
+            }
+        }
+    }
+}
+
+/// The license files of a package, compared with its `license` field
+pub struct LicenseFiles {
+    pub files: Vec<LicenseFile>,
+    pub mismatch: Option<Mismatch>,
+}
+
+impl LicenseFiles {
+    /// Read and recognize the license files of `pkg`. `NOTICE` files are listed,
+    /// but not recognized, as they aren't licenses.
+    pub fn check(pkg: &PkgDir) -> Self {
+        let mut files = Vec::new();
+        for path in license_files(pkg) {
+            let name = path.strip_prefix(&pkg.manifest_dir).unwrap_or(&path).to_string();
+            let notice =
+                path.file_name().is_some_and(|name| name.to_uppercase().starts_with("NOTICE"));
+            let recognized = match std::fs::read(&path) {
+                Ok(data) if !notice => identify(&String::from_utf8_lossy(&data)),
+                _ => None,
+            };
+            files.push(LicenseFile { name, recognized });
+        }
+        let mismatch = if files.is_empty() {
+            Some(Mismatch::NoFile)
+        } else if let PkgLicense::Expr(expr) = PkgLicense::of(&pkg.cm_pkg) {
+            let declared: Vec<&str> = expr.identifiers().into_iter().map(base_id).collect();
+            let mut found: Vec<&'static str> = files
+                .iter()
+                .flat_map(|file| file.recognized.iter().flat_map(|m| &m.ids))
+                .copied()
+                .filter(|id| !declared.contains(&base_id(id)))
+                .collect();
+            found.sort_unstable();
+            found.dedup();
+            (!found.is_empty()).then(|| Mismatch::Undeclared {
+                declared: expr.to_string(),
+                found,
+            })
+        } else {
+            None
+        };
+        Self { files, mismatch }
+    }
+}
+
+/// `id` without an exception and without the `-only`/`-or-later` suffix, for comparing
+/// declared licenses with recognized texts
+fn base_id(id: &str) -> &str {
+    let id = id.split(" WITH ").next().unwrap_or(id);
+    ["-only", "-or-later", "+"]
+        .iter()
+        .find_map(|suffix| id.strip_suffix(suffix))
+        .unwrap_or(id)
+}
@@ -0,0 +1,241 @@
+//! Building a reduced [`Metadata`] straight from a `Cargo.lock`, for when cargo can't be run
+//!
+//! The lockfile only records which packages are in the graph and how they are connected,
+//! so dependency requirements, kinds and enabled features are unknown. Packages get no
+//! dependency declarations, and the edges of the graph no kinds.
+//! Package info (description, license, etc.) is filled in from the unpacked sources
+//! in the cargo home directory, if they happen to be present.
+
+use {
+    anyhow::Context,
+    cargo_metadata::{camino::Utf8PathBuf, Metadata},
+    serde_json::{json, Value},
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+    },
+};
+
+#[derive(serde::Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<LockPackage>,
+}
+
+#[derive(serde::Deserialize)]
+struct LockPackage {
+    name: String,
+    version: String,
+    source: Option<String>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+impl LockPackage {
+    fn id(&self, root_dir: &Utf8PathBuf) -> String {
+        match &self.source {
+            Some(src) => format!("{src}#{}@{}", self.name, self.version),
+            None => format!("path+file://{root_dir}#{}@{}", self.name, self.version),
+        }
+    }
+}
+
+/// Fields of `[package]` in a manifest that are carried over into the package metadata
+const MANIFEST_FIELDS: &[&str] = &[
+    "description",
+    "license",
+    "license_file",
+    "repository",
+    "homepage",
+    "documentation",
+    "authors",
+    "keywords",
+    "categories",
+    "links",
+];
+
+/// Build the metadata for the lockfile at `lock_path`, along with warnings about
+/// the parts of it that couldn't be resolved
+pub fn metadata_from_lockfile(lock_path: &Path) -> anyhow::Result<(Metadata, Vec<String>)> {
+    let data = std::fs::read_to_string(lock_path)
+        .with_context(|| format!("Failed to read {}", lock_path.display()))?;
+    let lock: Lockfile = toml::from_str(&data).context("Failed to parse lockfile")?;
+    let root_dir = Utf8PathBuf::from_path_buf(std::path::absolute(lock_path)?)
+        .map_err(|path| anyhow::anyhow!("Non-UTF-8 path: {}", path.display()))?
+        .parent()
+        .context("Lockfile has no parent directory")?
+        .to_owned();
+    let root_manifest_path = root_dir.join("Cargo.toml");
+    let root_manifest = read_manifest(&root_manifest_path);
+    let root_name = root_manifest
+        .as_ref()
+        .and_then(|manifest| manifest.get("package")?.get("name")?.as_str());
+    let src_dirs = registry_src_dirs();
+    let mut by_name: HashMap<&str, Vec<&LockPackage>> = HashMap::new();
+    for pkg in &lock.package {
+        by_name.entry(&pkg.name).or_default().push(pkg);
+    }
+    let mut packages = Vec::new();
+    let mut nodes = Vec::new();
+    let mut workspace_members = Vec::new();
+    let mut root = None;
+    let mut warnings = Vec::new();
+    for pkg in &lock.package {
+        let id = pkg.id(&root_dir);
+        let is_root = pkg.source.is_none() && Some(pkg.name.as_str()) == root_name;
+        let manifest_path = if is_root {
+            Some(root_manifest_path.clone())
+        } else if pkg.source.is_some() {
+            src_dirs
+                .iter()
+                .map(|dir| dir.join(format!("{}-{}/Cargo.toml", pkg.name, pkg.version)))
+                .find(|path| path.exists())
+        } else {
+            None
+        };
+        let manifest = manifest_path.as_ref().and_then(read_manifest);
+        let mut node_deps = Vec::new();
+        for dep_spec in &pkg.dependencies {
+            let Some(dep) = resolve_dep_spec(dep_spec, &by_name) else {
+                warnings.push(format!(
+                    "Lockfile: Couldn't resolve dependency {dep_spec} of {}",
+                    pkg.name
+                ));
+                continue;
+            };
+            node_deps.push(json!({
+                "name": dep.name.replace('-', "_"),
+                "pkg": dep.id(&root_dir),
+                "dep_kinds": [],
+            }));
+        }
+        let mut package = json!({
+            "name": pkg.name,
+            "version": pkg.version,
+            "id": id,
+            "source": pkg.source,
+            "dependencies": [],
+            "targets": [],
+            "features": {},
+            // Packages without found sources still need some manifest path.
+            // Point it at a nonexistent file in the project root.
+            "manifest_path": manifest_path
+                .unwrap_or_else(|| root_dir.join(&pkg.name).join("Cargo.toml")),
+        });
+        if let Some(manifest) = &manifest {
+            copy_manifest_info(manifest, &mut package);
+        }
+        packages.push(package);
+        nodes.push(json!({
+            "id": id,
+            "dependencies": node_deps.iter().map(|dep| dep["pkg"].clone()).collect::<Vec<_>>(),
+            "deps": node_deps,
+            "features": [],
+        }));
+        // Path dependencies outside of the workspace can't be told apart from members here
+        if pkg.source.is_none() {
+            if is_root {
+                root = Some(id.clone());
+            }
+            workspace_members.push(id);
+        }
+    }
+    let metadata = json!({
+        "packages": packages,
+        "workspace_members": workspace_members,
+        "workspace_default_members": workspace_members,
+        "resolve": {
+            "nodes": nodes,
+            "root": root,
+        },
+        "workspace_root": root_dir,
+        "target_directory": root_dir.join("target"),
+        "version": 1,
+    });
+    Ok((serde_json::from_value(metadata)?, warnings))
+}
+
+/// The directory of the workspace that the manifest at `manifest_path` belongs to,
+/// which is where its `Cargo.lock` lives.
+///
+/// Like cargo, this follows `package.workspace`, or otherwise walks up the parent
+/// directories to the first manifest with a `[workspace]` table.
+pub fn workspace_root(manifest_path: &Path) -> anyhow::Result<PathBuf> {
+    let manifest_path = std::path::absolute(manifest_path)?;
+    let manifest_dir = manifest_path.parent().context("Manifest has no parent directory")?;
+    let manifest = read_manifest(&manifest_path);
+    if let Some(manifest) = &manifest {
+        if manifest.contains_key("workspace") {
+            return Ok(manifest_dir.to_owned());
+        }
+        if let Some(ws_path) =
+            manifest.get("package").and_then(|pkg| pkg.get("workspace")?.as_str())
+        {
+            return Ok(manifest_dir.join(ws_path));
+        }
+    }
+    let ws_dir = manifest_dir.ancestors().skip(1).find(|dir| {
+        read_manifest(dir.join("Cargo.toml"))
+            .is_some_and(|manifest| manifest.contains_key("workspace"))
+    });
+    Ok(ws_dir.unwrap_or(manifest_dir).to_owned())
+}
+
+/// Resolve a lockfile dependency entry, which is either `name`, `name version`,
+/// or `name version (source)`.
+fn resolve_dep_spec<'a>(
+    spec: &str,
+    by_name: &HashMap<&str, Vec<&'a LockPackage>>,
+) -> Option<&'a LockPackage> {
+    let mut parts = spec.split(' ');
+    let name = parts.next()?;
+    let version = parts.next();
+    let source = parts.next().and_then(|src| src.strip_prefix('(')?.strip_suffix(')'));
+    let candidates = by_name.get(name)?;
+    candidates.iter().copied().find(|pkg| {
+        version.is_none_or(|ver| pkg.version == ver)
+            && source.is_none_or(|src| pkg.source.as_deref() == Some(src))
+    })
+}
+
+fn read_manifest(path: impl AsRef<Path>) -> Option<toml::Table> {
+    let data = std::fs::read_to_string(path).ok()?;
+    toml::from_str(&data).ok()
+}
+
+fn copy_manifest_info(manifest: &toml::Table, package: &mut Value) {
+    if let Some(toml::Value::Table(pkg_table)) = manifest.get("package") {
+        for &field in MANIFEST_FIELDS {
+            // Manifests use kebab-case, while metadata uses snake_case
+            if let Some(value) = pkg_table.get(&field.replace('_', "-")) {
+                // Workspace inherited values (`{ workspace = true }`) can't be resolved here
+                if !value.is_table() {
+                    package[field] = serde_json::to_value(value).unwrap_or_default();
+                }
+            }
+        }
+    }
+    if let Some(features) = manifest.get("features") {
+        package["features"] = serde_json::to_value(features).unwrap_or_default();
+    }
+}
+
+/// `$CARGO_HOME`, or `~/.cargo`
+pub fn cargo_home() -> Option<Utf8PathBuf> {
+    match std::env::var("CARGO_HOME") {
+        Ok(home) => Some(Utf8PathBuf::from(home)),
+        Err(_) => directories::BaseDirs::new()
+            .and_then(|dirs| Utf8PathBuf::from_path_buf(dirs.home_dir().join(".cargo")).ok()),
+    }
+}
+
+/// The directories cargo unpacks registry packages into
+fn registry_src_dirs() -> Vec<Utf8PathBuf> {
+    let Some(cargo_home) = cargo_home() else {
+        return Vec::new();
+    };
+    let Ok(read_dir) = cargo_home.join("registry/src").read_dir_utf8() else {
+        return Vec::new();
+    };
+    read_dir.flatten().map(|entry| entry.into_path()).collect()
+}
@@ -1,8 +1,20 @@
+mod advisory;
 mod app;
 mod config;
+mod cost;
+mod duplicates;
+mod export;
+mod features;
+mod license;
+mod license_text;
+mod lockfile;
+mod notices;
 mod project;
+mod registry_index;
 mod style;
+mod target;
 mod ui;
+mod watch;
 
 use {
     app::App,
@@ -11,30 +23,155 @@ use {
     std::path::PathBuf,
 };
 
-#[derive(clap::Parser)]
+#[derive(clap::Parser, Clone, Default)]
 struct Args {
+    /// Path to the project directory or its `Cargo.toml`, instead of the current working directory
+    manifest_path: Option<PathBuf>,
+    #[command(flatten)]
+    resolve: ResolveArgs,
+    /// Load saved `cargo metadata --format-version 1` output from this file
+    /// instead of running cargo. Use `-` to read from stdin.
+    #[arg(long, value_name = "FILE")]
+    metadata_json: Option<PathBuf>,
+    /// Build a reduced project from `Cargo.lock` alone, without running cargo.
+    ///
+    /// Dependency kinds, requirements and enabled features are not available in this mode.
+    #[arg(long, conflicts_with = "metadata_json")]
+    lockfile_only: bool,
+    /// Only consider dependencies that apply to this target triple
+    #[arg(long, alias = "filter-platform")]
+    target: Option<String>,
+    /// Print the dependency graph in this format and exit, instead of opening the ui
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    export: Option<export::ExportFormat>,
+    /// Only export this package and the packages depending on it (`name` or `name@version`)
+    #[arg(
+        long,
+        value_name = "PKG",
+        requires = "export",
+        conflicts_with = "export_subtree"
+    )]
+    export_ancestors: Option<String>,
+    /// Only export this package and its dependencies (`name` or `name@version`)
+    #[arg(long, value_name = "PKG", requires = "export")]
+    export_subtree: Option<String>,
+    /// Write the export to this file instead of stdout
+    #[arg(long, value_name = "FILE", requires = "export")]
+    export_output: Option<PathBuf>,
+    /// Print third-party license notices in this format and exit, instead of opening the ui
+    #[arg(long, value_enum, value_name = "FORMAT", conflicts_with = "export")]
+    notices: Option<notices::NoticesFormat>,
+    /// Render the notices with this template instead of the built-in one
+    #[arg(long, value_name = "FILE", requires = "notices")]
+    notices_template: Option<PathBuf>,
+    /// Write the notices to this file instead of stdout
+    #[arg(long, value_name = "FILE", requires = "notices")]
+    notices_output: Option<PathBuf>,
+    /// Show version information and exit
+    #[arg(long)]
+    version: bool,
+}
+
+/// Options that are passed through to `cargo metadata`
+#[derive(clap::Args, Clone, Default)]
+struct ResolveArgs {
     /// Resolve without default features
     #[arg(long)]
     no_default_features: bool,
     /// Add features to the list of features to resolve
     #[arg(long)]
     features: Vec<String>,
-    /// Use this manifest path instead of the current working directory
-    manifest_path: Option<PathBuf>,
+    /// Resolve with all features enabled
+    #[arg(long)]
+    all_features: bool,
     /// Don't resolve dependencies
     #[arg(long)]
     no_deps: bool,
-    /// Show version information and exit
+    /// Run cargo without accessing the network
     #[arg(long)]
-    version: bool,
+    offline: bool,
+    /// Require Cargo.lock to be up to date
+    #[arg(long)]
+    locked: bool,
+    /// Require Cargo.lock to be up to date, and don't access the network
+    #[arg(long)]
+    frozen: bool,
+    /// Override a cargo configuration value (`KEY=VALUE`, or path to a config file)
+    #[arg(long, value_name = "KEY=VALUE")]
+    config: Vec<String>,
+    /// Path to the cargo binary to use
+    #[arg(long, value_name = "PATH")]
+    cargo_path: Option<PathBuf>,
+    /// Rustup toolchain to run cargo with. Can also be given as `+toolchain` first argument.
+    #[arg(long)]
+    toolchain: Option<String>,
+    /// Extra environment variable to set for cargo (`KEY=VALUE`)
+    #[arg(long, value_name = "KEY=VALUE")]
+    env: Vec<String>,
+}
+
+impl ResolveArgs {
+    /// Flags to pass to `cargo metadata`, in addition to the ones `MetadataCommand` handles
+    fn cargo_flags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+        for (enabled, flag) in [
+            (self.offline, "--offline"),
+            (self.locked, "--locked"),
+            (self.frozen, "--frozen"),
+        ] {
+            if enabled {
+                flags.push(flag.to_owned());
+            }
+        }
+        for cfg in &self.config {
+            flags.push("--config".to_owned());
+            flags.push(cfg.clone());
+        }
+        flags
+    }
+    /// The extra environment variables, split into key and value
+    fn env_vars(&self) -> anyhow::Result<Vec<(&str, &str)>> {
+        self.env
+            .iter()
+            .map(|var| {
+                var.split_once('=').ok_or_else(|| {
+                    anyhow::anyhow!("Invalid environment variable `{var}`. Expected KEY=VALUE")
+                })
+            })
+            .collect()
+    }
 }
 
 fn main() {
-    let args = Args::parse();
+    let mut cli_args: Vec<String> = std::env::args().collect();
+    // Support `ecargo +toolchain`, like cargo does
+    let toolchain = match cli_args.get(1) {
+        Some(arg) if arg.starts_with('+') => Some(cli_args.remove(1)[1..].to_owned()),
+        _ => None,
+    };
+    let mut args = Args::parse_from(cli_args);
+    if toolchain.is_some() {
+        args.resolve.toolchain = toolchain;
+    }
     if args.version {
         println!("ecargo version {}", env!("CARGO_PKG_VERSION"));
         return;
     }
+    let cli_result = match (args.export, args.notices) {
+        (Some(format), _) => Some(export::run_cli(&args, format)),
+        (_, Some(format)) => Some(notices::run_cli(&args, format)),
+        _ => None,
+    };
+    if let Some(result) = cli_result {
+        if let Err(e) = result {
+            match e.downcast_ref::<project::CargoError>() {
+                Some(cargo_err) => eprintln!("{cargo_err}\n{}", cargo_err.stderr),
+                None => eprintln!("Error: {e:#}"),
+            }
+            std::process::exit(1);
+        }
+        return;
+    }
     eframe::run_native(
         "ecargo",
         NativeOptions::default(),
@@ -44,10 +181,14 @@ fn main() {
                 cc.egui_ctx
                     .send_viewport_cmd(egui::ViewportCommand::InnerSize(egui::vec2(1280., 720.)));
                 let mut app = App::new(&cc.egui_ctx).unwrap();
-                if let Some(path) = &args.manifest_path {
+                if let Some(path) = &args.metadata_json {
+                    app.load_project_async(path.to_owned(), args);
+                } else if let Some(path) = &args.manifest_path {
                     app.load_project_async(path.to_owned(), args);
                 } else {
                     match std::env::current_dir() {
+                        // Show the welcome screen if we're not in a project directory
+                        Ok(cwd) if !cwd.join("Cargo.toml").exists() && !args.lockfile_only => {}
                         Ok(cwd) => app.load_project_async(cwd, args),
                         Err(e) => eprintln!("Could not determine cwd: {e}"),
                     }
@@ -0,0 +1,691 @@
+//! Third-party license notices, rendered from the license files of the dependencies
+//!
+//! Templates use a small subset of Mustache: `{{name}}` inserts a field, `{{#name}}...{{/name}}`
+//! repeats for each item of a list (or shows the contents if a text is not empty),
+//! `{{^name}}...{{/name}}` shows the contents if the field is empty, `{{.}}` is the current
+//! item of a list of texts and `{{! ... }}` is a comment.
+
+use {
+    crate::{
+        cost::downstream_packages,
+        license::PkgLicense,
+        license_text::identify,
+        project::{Pkg, PkgKey, Project},
+    },
+    anyhow::{bail, Context},
+    cargo_metadata::{camino::Utf8PathBuf, Package},
+    std::{
+        collections::{BTreeMap, HashMap},
+        io::Write as _,
+    },
+};
+
+/// Files in a package's directory with these prefixes (in uppercase) are license files
+const LICENSE_FILE_PREFIXES: &[&str] = &["LICENSE", "LICENCE", "COPYING", "NOTICE", "UNLICENSE"];
+/// Directory with one file per license, as used by REUSE
+const LICENSES_DIR: &str = "LICENSES";
+
+#[derive(Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum NoticesFormat {
+    #[default]
+    Markdown,
+    Html,
+    Text,
+}
+
+impl NoticesFormat {
+    pub const ALL: [Self; 3] = [Self::Markdown, Self::Html, Self::Text];
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Markdown => "Markdown",
+            Self::Html => "HTML",
+            Self::Text => "Text",
+        }
+    }
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+            Self::Text => "txt",
+        }
+    }
+    /// The format of a template file, going by its extension
+    pub fn of_path(path: &std::path::Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("md" | "markdown") => Self::Markdown,
+            Some("html" | "htm") => Self::Html,
+            _ => Self::Text,
+        }
+    }
+    pub fn builtin_template(self) -> &'static str {
+        match self {
+            Self::Markdown => include_str!("../assets/notices/template.md"),
+            Self::Html => include_str!("../assets/notices/template.html"),
+            Self::Text => include_str!("../assets/notices/template.txt"),
+        }
+    }
+    fn escape(self, text: &str) -> String {
+        match self {
+            Self::Html => {
+                let mut out = String::with_capacity(text.len());
+                for ch in text.chars() {
+                    match ch {
+                        '&' => out.push_str("&amp;"),
+                        '<' => out.push_str("&lt;"),
+                        '>' => out.push_str("&gt;"),
+                        '"' => out.push_str("&quot;"),
+                        '\'' => out.push_str("&#39;"),
+                        _ => out.push(ch),
+                    }
+                }
+                out
+            }
+            Self::Markdown | Self::Text => text.to_owned(),
+        }
+    }
+}
+
+/// A package and its directory, which is all it takes to find and read its license files.
+///
+/// Owned, so the files can be read on a separate thread.
+pub struct PkgDir {
+    pub key: PkgKey,
+    pub cm_pkg: Package,
+    pub manifest_dir: Utf8PathBuf,
+}
+
+impl PkgDir {
+    pub fn of(pkg: &Pkg) -> Self {
+        Self {
+            key: pkg.key,
+            cm_pkg: pkg.cm_pkg.clone(),
+            manifest_dir: pkg.manifest_dir.clone(),
+        }
+    }
+}
+
+/// The license files of a package, see [`Notices`]
+pub struct PkgNotice {
+    pub key: PkgKey,
+    /// Paths of the license files, relative to the package directory
+    pub files: Vec<String>,
+    /// Copyright lines found in the license files
+    pub copyrights: Vec<String>,
+}
+
+/// A license text shared by one or more packages, with the copyright lines taken out
+pub struct LicenseText {
+    pub text: String,
+    /// Names of the files the text was found in
+    pub file_names: Vec<String>,
+    pub packages: Vec<PkgKey>,
+}
+
+/// The license files of every third-party package that ends up in the build
+pub struct Notices {
+    /// Packages with license files, sorted by name and version
+    pub packages: Vec<PkgNotice>,
+    /// Sorted by the number of packages using them, most used first
+    pub texts: Vec<LicenseText>,
+    /// Packages where no license file was found
+    pub missing: Vec<PkgKey>,
+}
+
+impl Notices {
+    /// The third-party packages that the workspace members build, sorted by name and version.
+    ///
+    /// Dev dependencies are left out, as they don't end up in what is shipped.
+    pub fn shipped_packages(project: &Project) -> Vec<PkgDir> {
+        let mut keys: Vec<PkgKey> = project
+            .workspace_members
+            .iter()
+            .flat_map(|member| downstream_packages(project, *member))
+            .filter(|key| !project.is_workspace_member(*key))
+            .collect();
+        keys.sort_by(|a, b| {
+            let (a, b) = (&project.packages[*a].cm_pkg, &project.packages[*b].cm_pkg);
+            (&a.name, &a.version).cmp(&(&b.name, &b.version))
+        });
+        keys.dedup();
+        keys.into_iter().map(|key| PkgDir::of(&project.packages[key])).collect()
+    }
+    /// Read the license files of `packages`, see [`Notices::shipped_packages`]
+    pub fn collect(packages: &[PkgDir]) -> Self {
+        let mut notices = Self {
+            packages: Vec::new(),
+            texts: Vec::new(),
+            missing: Vec::new(),
+        };
+        // Index into `texts` by normalized text
+        let mut text_index: HashMap<String, usize> = HashMap::new();
+        for pkg in packages {
+            let key = pkg.key;
+            let mut pkg_notice = PkgNotice {
+                key,
+                files: Vec::new(),
+                copyrights: Vec::new(),
+            };
+            for path in license_files(pkg) {
+                let Ok(data) = std::fs::read(&path) else {
+                    continue;
+                };
+                let contents = String::from_utf8_lossy(&data);
+                let mut text = String::new();
+                for line in contents.lines() {
+                    if is_copyright_line(line) {
+                        let line = line.trim().to_owned();
+                        if !pkg_notice.copyrights.contains(&line) {
+                            pkg_notice.copyrights.push(line);
+                        }
+                    } else {
+                        text.push_str(line.trim_end());
+                        text.push('\n');
+                    }
+                }
+                let file_name = path.strip_prefix(&pkg.manifest_dir).unwrap_or(&path).to_string();
+                pkg_notice.files.push(file_name.clone());
+                let text = text.trim();
+                if text.is_empty() {
+                    continue;
+                }
+                let index = *text_index.entry(normalize(text)).or_insert_with(|| {
+                    notices.texts.push(LicenseText {
+                        text: text.to_owned(),
+                        file_names: Vec::new(),
+                        packages: Vec::new(),
+                    });
+                    notices.texts.len() - 1
+                });
+                let license_text = &mut notices.texts[index];
+                if !license_text.file_names.contains(&file_name) {
+                    license_text.file_names.push(file_name);
+                }
+                if license_text.packages.last() != Some(&key) {
+                    license_text.packages.push(key);
+                }
+            }
+            if pkg_notice.files.is_empty() {
+                notices.missing.push(key);
+            } else {
+                notices.packages.push(pkg_notice);
+            }
+        }
+        notices.texts.sort_by_key(|text| std::cmp::Reverse(text.packages.len()));
+        notices
+    }
+    /// A short name for a license text: the license it's recognized as, the license the packages
+    /// using it agree on, or else the name of the file it's in
+    pub fn title(&self, text: &LicenseText, project: &Project) -> String {
+        if let Some(recognized) = identify(&text.text) {
+            return recognized.ids.join(" and ");
+        }
+        let mut declared =
+            text.packages.iter().map(|key| {
+                project.packages[*key].cm_pkg.license.as_deref().filter(|license| {
+                    !license.contains(char::is_whitespace) && !license.contains('/')
+                })
+            });
+        match declared.next().flatten() {
+            Some(first) if declared.all(|license| license == Some(first)) => first.to_owned(),
+            _ => text.file_names.first().cloned().unwrap_or_default(),
+        }
+    }
+    /// Render `template`, escaping the inserted values for `format`
+    pub fn render(
+        &self,
+        project: &Project,
+        template: &str,
+        format: NoticesFormat,
+    ) -> anyhow::Result<String> {
+        let nodes = parse_template(template)?;
+        let mut out = String::new();
+        render_nodes(
+            &nodes,
+            &mut vec![&self.template_value(project)],
+            format,
+            &mut out,
+        )?;
+        Ok(out)
+    }
+    fn template_value(&self, project: &Project) -> Value {
+        let notices: HashMap<PkgKey, &PkgNotice> =
+            self.packages.iter().map(|notice| (notice.key, notice)).collect();
+        let pkg_value = |key: PkgKey| {
+            let pkg = &project.packages[key];
+            let mut fields = BTreeMap::from([
+                ("name", Value::Str(pkg.cm_pkg.name.clone())),
+                ("version", Value::Str(pkg.cm_pkg.version.to_string())),
+                (
+                    "license",
+                    Value::Str(PkgLicense::of(&pkg.cm_pkg).group_name()),
+                ),
+                (
+                    "repository",
+                    Value::Str(pkg.cm_pkg.repository.clone().unwrap_or_default()),
+                ),
+            ]);
+            if let Some(notice) = notices.get(&key) {
+                fields.insert("copyrights", Value::strings(&notice.copyrights));
+                fields.insert("files", Value::strings(&notice.files));
+            }
+            Value::Map(fields)
+        };
+        let project_name = match project.root {
+            Some(root) => project.packages[root].cm_pkg.name.clone(),
+            None => project.workspace_root.file_name().unwrap_or_default().to_owned(),
+        };
+        Value::Map(BTreeMap::from([
+            ("project", Value::Str(project_name)),
+            (
+                "groups",
+                Value::List(
+                    self.texts
+                        .iter()
+                        .map(|text| {
+                            Value::Map(BTreeMap::from([
+                                ("title", Value::Str(self.title(text, project))),
+                                ("text", Value::Str(text.text.clone())),
+                                ("files", Value::strings(&text.file_names)),
+                                (
+                                    "packages",
+                                    Value::List(
+                                        text.packages.iter().map(|key| pkg_value(*key)).collect(),
+                                    ),
+                                ),
+                            ]))
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "packages",
+                Value::List(self.packages.iter().map(|notice| pkg_value(notice.key)).collect()),
+            ),
+            (
+                "missing",
+                Value::List(self.missing.iter().map(|key| pkg_value(*key)).collect()),
+            ),
+            (
+                "has_missing",
+                Value::Str(if self.missing.is_empty() { "" } else { "yes" }.into()),
+            ),
+        ]))
+    }
+}
+
+/// The license files in the directory of `pkg`, and the one named by `license-file`
+pub fn license_files(pkg: &PkgDir) -> Vec<Utf8PathBuf> {
+    let mut files = Vec::new();
+    let mut scan = |dir: &Utf8PathBuf, all: bool| {
+        let Ok(entries) = dir.read_dir_utf8() else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_uppercase();
+            let is_file = entry.file_type().is_ok_and(|file_type| file_type.is_file());
+            if is_file
+                && (all || LICENSE_FILE_PREFIXES.iter().any(|prefix| name.starts_with(prefix)))
+            {
+                files.push(entry.into_path());
+            }
+        }
+    };
+    scan(&pkg.manifest_dir, false);
+    scan(&pkg.manifest_dir.join(LICENSES_DIR), true);
+    if let Some(file) = &pkg.cm_pkg.license_file {
+        let path = pkg.manifest_dir.join(file);
+        if path.is_file() && !files.contains(&path) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Whether `line` names a copyright holder, like `Copyright (c) 2016 Jane Doe`.
+///
+/// License texts mention copyright a lot, so a year (or at least a digit) is required,
+/// and placeholders like `[yyyy]` are left out.
+pub fn is_copyright_line(line: &str) -> bool {
+    let lower = line.to_lowercase();
+    // Comment markers and indentation
+    let lower =
+        lower.trim_start_matches(|ch: char| !ch.is_alphanumeric() && ch != '(' && ch != '©');
+    let starts = ["copyright", "(c)", "©"].iter().any(|prefix| lower.starts_with(prefix));
+    starts
+        && lower.contains(|ch: char| ch.is_ascii_digit())
+        && !["yyyy", "[year]", "{year}", "<year>"]
+            .iter()
+            .any(|placeholder| lower.contains(placeholder))
+}
+
+/// `text` with whitespace differences taken out, for comparing license texts
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Values that can be used in a template
+enum Value {
+    Str(String),
+    List(Vec<Value>),
+    Map(BTreeMap<&'static str, Value>),
+}
+
+impl Value {
+    fn strings(strings: &[String]) -> Self {
+        Self::List(strings.iter().map(|s| Self::Str(s.clone())).collect())
+    }
+    fn is_empty(&self) -> bool {
+        match self {
+            Self::Str(s) => s.is_empty(),
+            Self::List(items) => items.is_empty(),
+            Self::Map(_) => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TagKind {
+    Var,
+    Section,
+    Inverted,
+    Close,
+    Comment,
+}
+
+enum Piece {
+    Text(String),
+    Tag(TagKind, String),
+}
+
+enum Node {
+    Text(String),
+    Var(String),
+    Section {
+        name: String,
+        inverted: bool,
+        children: Vec<Node>,
+    },
+}
+
+fn parse_template(src: &str) -> anyhow::Result<Vec<Node>> {
+    let mut pieces = Vec::new();
+    let mut rest = src;
+    while let Some(start) = rest.find("{{") {
+        pieces.push(Piece::Text(rest[..start].to_owned()));
+        let line = src[..src.len() - rest.len() + start].matches('\n').count() + 1;
+        let Some(len) = rest[start..].find("}}") else {
+            bail!("Unclosed tag on line {line}");
+        };
+        let tag = rest[start + 2..start + len].trim();
+        let (kind, name) = match tag.chars().next() {
+            Some('#') => (TagKind::Section, &tag[1..]),
+            Some('^') => (TagKind::Inverted, &tag[1..]),
+            Some('/') => (TagKind::Close, &tag[1..]),
+            Some('!') => (TagKind::Comment, ""),
+            _ => (TagKind::Var, tag),
+        };
+        if kind != TagKind::Comment && name.trim().is_empty() {
+            bail!("Empty tag on line {line}");
+        }
+        pieces.push(Piece::Tag(kind, name.trim().to_owned()));
+        rest = &rest[start + len + 2..];
+    }
+    pieces.push(Piece::Text(rest.to_owned()));
+    strip_standalone_tags(&mut pieces);
+    let mut stack: Vec<(String, bool, Vec<Node>)> = vec![(String::new(), false, Vec::new())];
+    for piece in pieces {
+        let node = match piece {
+            Piece::Text(text) if text.is_empty() => continue,
+            Piece::Text(text) => Node::Text(text),
+            Piece::Tag(TagKind::Var, name) => Node::Var(name),
+            Piece::Tag(TagKind::Comment, _) => continue,
+            Piece::Tag(kind @ (TagKind::Section | TagKind::Inverted), name) => {
+                stack.push((name, kind == TagKind::Inverted, Vec::new()));
+                continue;
+            }
+            Piece::Tag(TagKind::Close, name) => {
+                let (open, inverted, children) = stack.pop().context("Unbalanced sections")?;
+                if stack.is_empty() {
+                    bail!("`{{{{/{name}}}}}` closes a section that was never opened");
+                }
+                if open != name {
+                    bail!("`{{{{/{name}}}}}` closes section `{open}`");
+                }
+                Node::Section {
+                    name,
+                    inverted,
+                    children,
+                }
+            }
+        };
+        stack.last_mut().context("Unbalanced sections")?.2.push(node);
+    }
+    let (name, _, nodes) = stack.pop().context("Unbalanced sections")?;
+    if !stack.is_empty() {
+        bail!("Section `{name}` is never closed");
+    }
+    Ok(nodes)
+}
+
+/// Remove the lines of section and comment tags that are alone on their line,
+/// so they don't leave empty lines in the output
+fn strip_standalone_tags(pieces: &mut [Piece]) {
+    // Index of the last text piece known to start at the beginning of a line
+    let mut line_start = 0;
+    for i in 0..pieces.len() {
+        if !matches!(&pieces[i], Piece::Tag(kind, _) if *kind != TagKind::Var) {
+            continue;
+        }
+        let before = match i.checked_sub(1).map(|j| &pieces[j]) {
+            Some(Piece::Text(text)) => {
+                let tail = text.rsplit('\n').next().unwrap_or_default();
+                tail.trim().is_empty() && (text.contains('\n') || i - 1 == line_start)
+            }
+            _ => false,
+        };
+        let after = match pieces.get(i + 1) {
+            Some(Piece::Text(text)) => {
+                let head = text.split('\n').next().unwrap_or_default();
+                head.trim().is_empty() && (text.contains('\n') || i + 2 == pieces.len())
+            }
+            _ => false,
+        };
+        if !(before && after) {
+            continue;
+        }
+        if let Piece::Text(text) = &mut pieces[i - 1] {
+            text.truncate(text.rfind('\n').map_or(0, |pos| pos + 1));
+        }
+        if let Piece::Text(text) = &mut pieces[i + 1] {
+            text.drain(..text.find('\n').map_or(text.len(), |pos| pos + 1));
+        }
+        line_start = i + 1;
+    }
+}
+
+fn lookup<'a>(name: &str, stack: &[&'a Value]) -> anyhow::Result<&'a Value> {
+    if name == "." {
+        return stack.last().copied().context("No current item");
+    }
+    stack
+        .iter()
+        .rev()
+        .find_map(|value| match value {
+            Value::Map(fields) => fields.get(name),
+            _ => None,
+        })
+        .with_context(|| format!("Unknown field `{name}`"))
+}
+
+fn render_nodes<'a>(
+    nodes: &'a [Node],
+    stack: &mut Vec<&'a Value>,
+    format: NoticesFormat,
+    out: &mut String,
+) -> anyhow::Result<()> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var(name) => match lookup(name, stack)? {
+                Value::Str(s) => out.push_str(&format.escape(s)),
+                _ => bail!("`{name}` is a list, and has to be used as a section"),
+            },
+            Node::Section {
+                name,
+                inverted: true,
+                children,
+            } => {
+                if lookup(name, stack)?.is_empty() {
+                    render_nodes(children, stack, format, out)?;
+                }
+            }
+            Node::Section {
+                name,
+                inverted: false,
+                children,
+            } => match lookup(name, stack)? {
+                Value::List(items) => {
+                    for item in items {
+                        stack.push(item);
+                        render_nodes(children, stack, format, out)?;
+                        stack.pop();
+                    }
+                }
+                value if !value.is_empty() => render_nodes(children, stack, format, out)?,
+                _ => {}
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Write the notices for the project selected by `args`, for `ecargo --notices`
+pub fn run_cli(args: &crate::Args, format: NoticesFormat) -> anyhow::Result<()> {
+    let path = match args.metadata_json.as_ref().or(args.manifest_path.as_ref()) {
+        Some(path) => path.clone(),
+        None => std::env::current_dir()?,
+    };
+    let project = Project::load_blocking(&path, args)?;
+    for warning in &project.warnings {
+        eprintln!("Warning: {warning}");
+    }
+    let template = match &args.notices_template {
+        Some(path) => std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?,
+        None => format.builtin_template().to_owned(),
+    };
+    let notices = Notices::collect(&Notices::shipped_packages(&project));
+    let out = notices.render(&project, &template, format).context("Invalid template")?;
+    match &args.notices_output {
+        Some(path) => std::fs::write(path, out)
+            .with_context(|| format!("Failed to write {}", path.display()))?,
+        None => std::io::stdout().write_all(out.as_bytes())?,
+    }
+    if !notices.missing.is_empty() {
+        eprintln!(
+            "No license file found for {} packages:",
+            notices.missing.len()
+        );
+        for key in &notices.missing {
+            let pkg = &project.packages[*key].cm_pkg;
+            eprintln!(
+                "  {} {} ({})",
+                pkg.name,
+                pkg.version,
+                PkgLicense::of(pkg).group_name()
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_template, render_nodes, NoticesFormat, Value};
+    use std::collections::BTreeMap;
+
+    fn value() -> Value {
+        Value::Map(BTreeMap::from([
+            ("project", Value::Str("demo".into())),
+            ("items", Value::strings(&["x".into(), "<y>".into()])),
+            ("empty", Value::List(Vec::new())),
+            (
+                "packages",
+                Value::List(vec![Value::Map(BTreeMap::from([(
+                    "name",
+                    Value::Str("foo".into()),
+                )]))]),
+            ),
+        ]))
+    }
+
+    fn render(template: &str, format: NoticesFormat) -> anyhow::Result<String> {
+        let nodes = parse_template(template)?;
+        let value = value();
+        let mut out = String::new();
+        render_nodes(&nodes, &mut vec![&value], format, &mut out)?;
+        Ok(out)
+    }
+
+    fn render_text(template: &str) -> String {
+        render(template, NoticesFormat::Text).unwrap()
+    }
+
+    #[test]
+    fn sections_iterate_lists() {
+        assert_eq!(render_text("{{#items}}[{{.}}]{{/items}}"), "[x][<y>]");
+        assert_eq!(render_text("{{#empty}}never{{/empty}}"), "");
+        // Fields of outer values are visible inside sections
+        assert_eq!(
+            render_text("{{#packages}}{{name}} in {{project}}{{/packages}}"),
+            "foo in demo"
+        );
+    }
+
+    #[test]
+    fn inverted_sections_render_when_empty() {
+        assert_eq!(render_text("{{^empty}}none{{/empty}}"), "none");
+        assert_eq!(render_text("{{^items}}none{{/items}}"), "");
+    }
+
+    #[test]
+    fn values_are_escaped_for_html() {
+        assert_eq!(
+            render("{{#items}}{{.}} {{/items}}", NoticesFormat::Html).unwrap(),
+            "x &lt;y&gt; "
+        );
+    }
+
+    #[test]
+    fn standalone_tag_lines_are_removed() {
+        let template = "Items:\n{{! a comment }}\n{{#items}}\n- {{.}}\n{{/items}}\nEnd\n";
+        assert_eq!(render_text(template), "Items:\n- x\n- <y>\nEnd\n");
+        // At the very start and end of the template too
+        assert_eq!(render_text("{{#items}}\n{{.}}\n{{/items}}"), "x\n<y>\n");
+    }
+
+    #[test]
+    fn inline_tags_keep_their_line() {
+        assert_eq!(render_text("a {{#empty}}b{{/empty}}\nc"), "a \nc");
+        assert_eq!(render_text("{{project}}\n"), "demo\n");
+    }
+
+    #[test]
+    fn invalid_templates() {
+        for template in [
+            "{{project",
+            "{{}}",
+            "{{#items}}",
+            "{{/items}}",
+            "{{#items}}{{/empty}}",
+        ] {
+            assert!(
+                parse_template(template).is_err(),
+                "{template:?} should not parse"
+            );
+        }
+        assert!(render("{{items}}", NoticesFormat::Text).is_err());
+        assert!(render("{{unknown}}", NoticesFormat::Text).is_err());
+    }
+}
@@ -1,17 +1,88 @@
 use {
-    crate::app::{LoadSend, LoadStage},
-    cargo_metadata::{camino::Utf8PathBuf, CargoOpt, DependencyKind, MetadataCommand, Package},
+    crate::{
+        app::{LoadRecv, LoadSend, LoadStage},
+        target::TargetCfg,
+    },
+    anyhow::Context,
+    cargo_metadata::{
+        camino::Utf8PathBuf, semver::Version, CargoOpt, Dependency, DependencyKind, Metadata,
+        MetadataCommand, Package, PackageId, Resolve,
+    },
     cargo_platform::Platform,
     slotmap::{new_key_type, SlotMap},
-    std::{collections::HashMap, path::Path},
+    std::{
+        collections::HashMap,
+        io::{BufRead, BufReader, Read},
+        path::{Path, PathBuf},
+        process::{ExitStatus, Stdio},
+        sync::atomic::{AtomicBool, Ordering},
+        time::Duration,
+    },
 };
 
 pub struct DepLink {
     pub pkg_key: PkgKey,
+    /// Every (kind, target) combination this dependency edge was declared with
+    pub kinds: Vec<LinkKind>,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct LinkKind {
     pub kind: DependencyKind,
     pub target: Option<Platform>,
 }
 
+impl LinkKind {
+    pub fn matches_dep(&self, dep: &Dependency) -> bool {
+        self.kind == dep.kind && self.target == dep.target
+    }
+    /// Whether this kind of dependency applies when building for `target`
+    pub fn applies_to(&self, target: Option<&TargetCfg>) -> bool {
+        match (&self.target, target) {
+            (Some(platform), Some(target)) => target.matches(platform),
+            _ => true,
+        }
+    }
+}
+
+/// What a package is needed for, see [`Project::build_contexts`]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BuildContext {
+    Normal,
+    Build,
+    Dev,
+}
+
+impl BuildContext {
+    pub fn of_kind(kind: DependencyKind) -> Self {
+        match kind {
+            DependencyKind::Build => Self::Build,
+            DependencyKind::Development => Self::Dev,
+            _ => Self::Normal,
+        }
+    }
+    /// The least restricted context of the kinds of `link`
+    pub fn of_link(link: &DepLink) -> Self {
+        link.kinds
+            .iter()
+            .map(|kind| Self::of_kind(kind.kind))
+            .min()
+            .unwrap_or(Self::Normal)
+    }
+}
+
+impl DepLink {
+    /// Links without kinds (with `--lockfile-only`) are assumed to apply everywhere
+    pub fn applies_to(&self, target: Option<&TargetCfg>) -> bool {
+        self.kinds.is_empty() || self.kinds.iter().any(|kind| kind.applies_to(target))
+    }
+    /// Whether the dependency is only a dev dependency. `false` if that's unknown.
+    pub fn is_dev_only(&self) -> bool {
+        !self.kinds.is_empty()
+            && self.kinds.iter().all(|kind| kind.kind == DependencyKind::Development)
+    }
+}
+
 pub struct Pkg {
     pub cm_pkg: Package,
     pub key: PkgKey,
@@ -21,6 +92,8 @@ pub struct Pkg {
     pub manifest_dir: Utf8PathBuf,
     pub readme_path: Option<Utf8PathBuf>,
     pub changelog_path: Option<Utf8PathBuf>,
+    /// Whether this package is part of the build for the selected target
+    pub on_target: bool,
 }
 
 pub type PkgSlotMap = SlotMap<PkgKey, Pkg>;
@@ -28,7 +101,16 @@ pub type PkgSlotMap = SlotMap<PkgKey, Pkg>;
 pub struct Project {
     pub packages: PkgSlotMap,
     pub root: Option<PkgKey>,
-    pub license_map: HashMap<String, Vec<PkgKey>>,
+    pub workspace_members: Vec<PkgKey>,
+    /// Members that are operated on when no package is selected (`workspace.default-members`)
+    pub workspace_default_members: Vec<PkgKey>,
+    /// The target selected for platform filtering. `None` means all platforms.
+    pub target: Option<TargetCfg>,
+    pub workspace_root: Utf8PathBuf,
+    /// Built from `Cargo.lock` alone, so dependency requirements, kinds and features are unknown
+    pub lockfile_only: bool,
+    /// Problems found while loading that didn't prevent the project from loading
+    pub warnings: Vec<String>,
 }
 
 new_key_type! {
@@ -36,24 +118,122 @@ new_key_type! {
 }
 
 impl Project {
-    pub fn load(path: &Path, args: &crate::Args, sender: LoadSend) -> anyhow::Result<()> {
-        let mut cmd = MetadataCommand::new();
-        cmd.manifest_path(path.join("Cargo.toml"));
-        if args.no_default_features {
-            cmd.features(CargoOpt::NoDefaultFeatures);
-        }
-        if !args.features.is_empty() {
-            cmd.features(CargoOpt::SomeFeatures(args.features.to_owned()));
-        }
-        if args.no_deps {
-            cmd.no_deps();
-        }
-        sender.send(LoadStage::MetadataQuery)?;
-        let metadata = cmd.exec()?;
+    pub fn load(
+        path: &Path,
+        args: &crate::Args,
+        sender: LoadSend,
+        cancel: &AtomicBool,
+    ) -> anyhow::Result<()> {
+        let mut warnings = Vec::new();
+        let metadata = if let Some(json_path) = &args.metadata_json {
+            sender.send(LoadStage::ReadMetadataJson)?;
+            read_metadata_json(json_path)?
+        } else if args.lockfile_only {
+            sender.send(LoadStage::ReadLockfile)?;
+            let lock_path = if path.file_name().is_some_and(|name| name == "Cargo.lock") {
+                path.to_owned()
+            } else {
+                crate::lockfile::workspace_root(&manifest_path(path))?.join("Cargo.lock")
+            };
+            let metadata;
+            (metadata, warnings) = crate::lockfile::metadata_from_lockfile(&lock_path)?;
+            metadata
+        } else {
+            let mut cmd = MetadataCommand::new();
+            cmd.manifest_path(manifest_path(path));
+            let resolve = &args.resolve;
+            if resolve.no_default_features {
+                cmd.features(CargoOpt::NoDefaultFeatures);
+            }
+            if resolve.all_features {
+                cmd.features(CargoOpt::AllFeatures);
+            }
+            if !resolve.features.is_empty() {
+                cmd.features(CargoOpt::SomeFeatures(resolve.features.to_owned()));
+            }
+            if resolve.no_deps {
+                cmd.no_deps();
+            }
+            if let Some(cargo_path) = &resolve.cargo_path {
+                cmd.cargo_path(cargo_path);
+            }
+            // The rustup proxy would need `+toolchain` as the very first argument,
+            // but this has the same effect.
+            if let Some(toolchain) = &resolve.toolchain {
+                cmd.env("RUSTUP_TOOLCHAIN", toolchain);
+            }
+            for (key, value) in resolve.env_vars()? {
+                cmd.env(key, value);
+            }
+            cmd.other_options(resolve.cargo_flags());
+            sender.send(LoadStage::MetadataQuery)?;
+            exec_metadata_cmd(&cmd, &sender, cancel)?
+        };
+        Self::from_metadata(metadata, warnings, args, sender)
+    }
+    /// Load the project on the current thread, without reporting progress
+    pub fn load_blocking(path: &Path, args: &crate::Args) -> anyhow::Result<Self> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        Self::load(path, args, tx, &AtomicBool::new(false))?;
+        Self::received(&rx)
+    }
+    /// Build the project from `metadata` on the current thread, without reporting progress
+    #[cfg(test)]
+    pub(crate) fn from_metadata_blocking(
+        metadata: Metadata,
+        args: &crate::Args,
+    ) -> anyhow::Result<Self> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        Self::from_metadata(metadata, Vec::new(), args, tx)?;
+        Self::received(&rx)
+    }
+    /// The project sent to `recv` when loading finished
+    fn received(recv: &LoadRecv) -> anyhow::Result<Self> {
+        recv.try_iter()
+            .find_map(|stage| match stage {
+                LoadStage::Finished(project) => Some(project),
+                _ => None,
+            })
+            .context("Loading finished without a project")
+    }
+    /// Load the project on the current thread, forwarding the progress to `sender`
+    pub fn load_forwarding(
+        path: &Path,
+        args: &crate::Args,
+        sender: &LoadSend,
+        cancel: &AtomicBool,
+    ) -> anyhow::Result<Self> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::scope(|scope| {
+            let forwarder = scope.spawn(move || {
+                let mut project = None;
+                for stage in rx {
+                    match stage {
+                        LoadStage::Finished(proj) => project = Some(proj),
+                        // Nobody is listening anymore if the load was cancelled
+                        stage => _ = sender.send(stage),
+                    }
+                }
+                project
+            });
+            let result = Self::load(path, args, tx, cancel);
+            let project = forwarder.join().expect("Forwarding thread panicked");
+            result?;
+            project.context("Loading finished without a project")
+        })
+    }
+    fn from_metadata(
+        metadata: Metadata,
+        warnings: Vec<String>,
+        args: &crate::Args,
+        sender: LoadSend,
+    ) -> anyhow::Result<()> {
         sender.send(LoadStage::PkgInfoCollect)?;
         let mut packages = SlotMap::with_key();
         let mut pkgid_key_mappings = HashMap::new();
-        for package in &metadata.packages {
+        let total = metadata.packages.len();
+        for (i, package) in metadata.packages.iter().enumerate() {
+            report_progress(&sender, i, total)?;
             packages.insert_with_key(|key| {
                 pkgid_key_mappings.insert(package.id.clone(), key);
                 let manifest_dir = package.manifest_path.parent().unwrap().to_owned();
@@ -68,72 +248,521 @@ impl Project {
                     manifest_dir,
                     readme_path: readme_path.exists().then_some(readme_path),
                     changelog_path: changelog_path.exists().then_some(changelog_path),
+                    on_target: true,
                 }
             });
         }
+        finish_progress(&sender, total)?;
         sender.send(LoadStage::Resolve)?;
         if let Some(resolve) = metadata.resolve.as_ref() {
-            for node in &resolve.nodes {
+            let total = resolve.nodes.len();
+            for (i, node) in resolve.nodes.iter().enumerate() {
+                report_progress(&sender, i, total)?;
                 let pkg_key = pkgid_key_mappings[&node.id];
                 packages[pkg_key].enabled_features.clone_from(&node.features);
             }
+            finish_progress(&sender, total)?;
         }
-        // Collect dependents
         sender.send(LoadStage::GenDepGraph)?;
-        gen_dep_graph_info(&mut packages);
-        let root;
-        match metadata.root_package() {
-            Some(pkg) => {
-                if let Some(pkg) = packages.values().find(|pkg_inner| pkg_inner.cm_pkg == *pkg) {
-                    root = Some(pkg.key);
-                } else {
-                    root = None;
-                }
+        match metadata.resolve.as_ref() {
+            Some(resolve) => {
+                gen_dep_graph_info(&mut packages, resolve, &pkgid_key_mappings, &sender)?
             }
-            None => root = None,
+            // With `--no-deps`, there is no resolve information, so we can only make
+            // a best guess based on the dependency requirements of the workspace members
+            None => gen_dep_graph_info_unresolved(&mut packages, &sender)?,
         }
-        sender.send(LoadStage::Finished(Project {
+        let root = metadata.root_package().map(|pkg| pkgid_key_mappings[&pkg.id]);
+        let workspace_members: Vec<PkgKey> =
+            metadata.workspace_members.iter().map(|id| pkgid_key_mappings[id]).collect();
+        // Older cargo versions don't report default members
+        let workspace_default_members = if cargo_metadata::workspace_default_members_is_missing(
+            &metadata.workspace_default_members,
+        ) {
+            workspace_members.clone()
+        } else {
+            metadata
+                .workspace_default_members
+                .iter()
+                .map(|id| pkgid_key_mappings[id])
+                .collect()
+        };
+        let mut project = Project {
             packages,
             root,
-            license_map: HashMap::new(),
-        }))?;
+            workspace_members,
+            workspace_default_members,
+            target: None,
+            workspace_root: metadata.workspace_root.clone(),
+            lockfile_only: args.metadata_json.is_none() && args.lockfile_only,
+            warnings,
+        };
+        if let Some(triple) = &args.target {
+            project.set_target(Some(TargetCfg::query(triple)?));
+        }
+        sender.send(LoadStage::Finished(project))?;
         Ok(())
     }
+    /// Set the target to filter for, and determine which packages are built for it
+    pub fn set_target(&mut self, target: Option<TargetCfg>) {
+        self.target = target;
+        let Some(target) = &self.target else {
+            for pkg in self.packages.values_mut() {
+                pkg.on_target = true;
+            }
+            return;
+        };
+        for pkg in self.packages.values_mut() {
+            pkg.on_target = false;
+        }
+        let mut stack = self.workspace_members.clone();
+        while let Some(key) = stack.pop() {
+            let pkg = &mut self.packages[key];
+            if pkg.on_target {
+                continue;
+            }
+            pkg.on_target = true;
+            stack.extend(
+                pkg.dependencies
+                    .iter()
+                    .filter(|link| link.applies_to(Some(target)))
+                    .map(|link| link.pkg_key),
+            );
+        }
+    }
+    /// How each package is reached from the workspace members.
+    ///
+    /// Packages reachable through normal dependencies alone are [`BuildContext::Normal`],
+    /// the ones that additionally need build dependencies are [`BuildContext::Build`],
+    /// and the rest are only used by dev dependencies.
+    pub fn build_contexts(&self) -> HashMap<PkgKey, BuildContext> {
+        let mut contexts: HashMap<PkgKey, BuildContext> =
+            self.workspace_members.iter().map(|key| (*key, BuildContext::Normal)).collect();
+        for context in [BuildContext::Normal, BuildContext::Build, BuildContext::Dev] {
+            // Continue from everything reached so far, following more kinds of edges each round
+            let mut stack: Vec<PkgKey> = contexts.keys().copied().collect();
+            while let Some(key) = stack.pop() {
+                for link in &self.packages[key].dependencies {
+                    if BuildContext::of_link(link) <= context
+                        && !contexts.contains_key(&link.pkg_key)
+                    {
+                        contexts.insert(link.pkg_key, context);
+                        stack.push(link.pkg_key);
+                    }
+                }
+            }
+        }
+        contexts
+    }
+    /// The files that determine the dependency graph: member manifests and the lockfile
+    pub fn graph_input_files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = self
+            .workspace_members
+            .iter()
+            .map(|key| self.packages[*key].cm_pkg.manifest_path.clone().into())
+            .collect();
+        files.push(self.workspace_root.join("Cargo.toml").into());
+        files.push(self.workspace_root.join("Cargo.lock").into());
+        files.sort();
+        files.dedup();
+        files
+    }
+    /// Find the package that best corresponds to `pkg` of another load of this project.
+    ///
+    /// This is the package with the same id, or failing that, the only package with the same name.
+    pub fn corresponding_pkg(&self, pkg: &Pkg) -> Option<PkgKey> {
+        if let Some(same) = self.packages.values().find(|p| p.cm_pkg.id == pkg.cm_pkg.id) {
+            return Some(same.key);
+        }
+        let mut same_name = self.packages.values().filter(|p| p.cm_pkg.name == pkg.cm_pkg.name);
+        match (same_name.next(), same_name.next()) {
+            (Some(only), None) => Some(only.key),
+            _ => None,
+        }
+    }
+    /// The packages and enabled features that differ between `self` and `other`.
+    ///
+    /// Packages are identified by name and version.
+    pub fn diff(&self, other: &Project) -> ProjectDiff {
+        let index = |project: &Project| -> HashMap<(String, Version), Vec<String>> {
+            project
+                .packages
+                .values()
+                .map(|pkg| {
+                    let mut features = pkg.enabled_features.clone();
+                    features.sort();
+                    (
+                        (pkg.cm_pkg.name.clone(), pkg.cm_pkg.version.clone()),
+                        features,
+                    )
+                })
+                .collect()
+        };
+        let (ours, theirs) = (index(self), index(other));
+        let mut diff = ProjectDiff::default();
+        for (id, features) in &ours {
+            match theirs.get(id) {
+                None => diff.only_in_self.push(id.clone()),
+                Some(their_features) if their_features != features => {
+                    diff.feature_changes.push(FeatureChange {
+                        name: id.0.clone(),
+                        version: id.1.clone(),
+                        only_in_self: features
+                            .iter()
+                            .filter(|feat| !their_features.contains(feat))
+                            .cloned()
+                            .collect(),
+                        only_in_other: their_features
+                            .iter()
+                            .filter(|feat| !features.contains(feat))
+                            .cloned()
+                            .collect(),
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+        diff.only_in_other = theirs.into_keys().filter(|id| !ours.contains_key(id)).collect();
+        diff.only_in_self.sort();
+        diff.only_in_other.sort();
+        diff.feature_changes
+            .sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+        diff
+    }
+    /// Find a package by name and version
+    pub fn find_pkg(&self, name: &str, version: &Version) -> Option<PkgKey> {
+        self.packages
+            .values()
+            .find(|pkg| pkg.cm_pkg.name == name && pkg.cm_pkg.version == *version)
+            .map(|pkg| pkg.key)
+    }
+    /// A short user-facing summary of how the packages changed compared to `old`,
+    /// e.g. "+3 added, 2 upgraded"
+    pub fn change_summary(&self, old: &Project) -> String {
+        let (new_versions, old_versions) = (self.versions_by_name(), old.versions_by_name());
+        let [mut added, mut removed, mut upgraded, mut downgraded, mut changed] = [0; 5];
+        for (name, versions) in &new_versions {
+            match old_versions.get(name) {
+                None => added += 1,
+                Some(old_versions) if old_versions != versions => {
+                    match versions.last().cmp(&old_versions.last()) {
+                        std::cmp::Ordering::Greater => upgraded += 1,
+                        std::cmp::Ordering::Less => downgraded += 1,
+                        std::cmp::Ordering::Equal => changed += 1,
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+        removed += old_versions.keys().filter(|name| !new_versions.contains_key(*name)).count();
+        let parts: Vec<String> = [
+            (added, "+", "added"),
+            (removed, "-", "removed"),
+            (upgraded, "", "upgraded"),
+            (downgraded, "", "downgraded"),
+            (changed, "", "changed"),
+        ]
+        .into_iter()
+        .filter(|(n, ..)| *n != 0)
+        .map(|(n, sign, what)| format!("{sign}{n} {what}"))
+        .collect();
+        if parts.is_empty() {
+            "No package changes".into()
+        } else {
+            parts.join(", ")
+        }
+    }
+    /// All the versions of each package name, sorted
+    pub fn versions_by_name(&self) -> HashMap<String, Vec<Version>> {
+        let mut map: HashMap<String, Vec<Version>> = HashMap::new();
+        for pkg in self.packages.values() {
+            map.entry(pkg.cm_pkg.name.clone()).or_default().push(pkg.cm_pkg.version.clone());
+        }
+        for versions in map.values_mut() {
+            versions.sort();
+        }
+        map
+    }
+    pub fn is_workspace_member(&self, key: PkgKey) -> bool {
+        self.workspace_members.contains(&key)
+    }
+    /// The package to show when the project is opened
+    pub fn initial_pkg(&self) -> Option<PkgKey> {
+        self.root
+            .or_else(|| self.workspace_default_members.first().copied())
+            .or_else(|| self.workspace_members.first().copied())
+    }
+    /// Whether `link` applies to the currently selected target
+    pub fn link_on_target(&self, link: &DepLink) -> bool {
+        link.applies_to(self.target.as_ref()) && self.packages[link.pkg_key].on_target
+    }
+    /// The features `from` requests of its dependency `to`, including `default`.
+    ///
+    /// With `kind`, only the declarations of that kind and platform are considered.
+    pub fn edge_features(&self, from: PkgKey, to: PkgKey, kind: Option<&LinkKind>) -> Vec<String> {
+        let from = &self.packages[from];
+        let mut features = Vec::new();
+        for decl in &from.cm_pkg.dependencies {
+            if kind.is_some_and(|kind| !kind.matches_dep(decl))
+                || from.resolved_dep(decl, &self.packages).map(|pkg| pkg.key) != Some(to)
+            {
+                continue;
+            }
+            if decl.uses_default_features && !features.iter().any(|feat| feat == "default") {
+                features.push("default".to_owned());
+            }
+            for feat in &decl.features {
+                if !features.contains(feat) {
+                    features.push(feat.clone());
+                }
+            }
+        }
+        features
+    }
+}
+
+/// Differences between two loads of a project, see [`Project::diff`]
+#[derive(Default)]
+pub struct ProjectDiff {
+    /// Name and version of packages that are only in the first project
+    pub only_in_self: Vec<(String, Version)>,
+    /// Name and version of packages that are only in the second project
+    pub only_in_other: Vec<(String, Version)>,
+    /// Packages that are in both, but with different features enabled
+    pub feature_changes: Vec<FeatureChange>,
+}
+
+impl ProjectDiff {
+    pub fn is_empty(&self) -> bool {
+        self.only_in_self.is_empty()
+            && self.only_in_other.is_empty()
+            && self.feature_changes.is_empty()
+    }
+}
+
+pub struct FeatureChange {
+    pub name: String,
+    pub version: Version,
+    pub only_in_self: Vec<String>,
+    pub only_in_other: Vec<String>,
+}
+
+/// How many items are processed between progress reports
+const PROGRESS_INTERVAL: usize = 64;
+
+fn report_progress(sender: &LoadSend, done: usize, total: usize) -> anyhow::Result<()> {
+    if done.is_multiple_of(PROGRESS_INTERVAL) {
+        sender.send(LoadStage::Progress { done, total })?;
+    }
+    Ok(())
+}
+
+/// Report that all `total` items of the current stage are done
+fn finish_progress(sender: &LoadSend, total: usize) -> anyhow::Result<()> {
+    sender.send(LoadStage::Progress { done: total, total })?;
+    Ok(())
+}
+
+/// `cargo metadata` exited unsuccessfully
+#[derive(Debug)]
+pub struct CargoError {
+    pub status: ExitStatus,
+    pub stderr: String,
+}
+
+impl std::fmt::Display for CargoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cargo metadata failed ({})", self.status)
+    }
+}
+
+impl std::error::Error for CargoError {}
+
+/// Loading was cancelled by the user
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Cancelled")
+    }
 }
 
-pub fn dep_matches_pkg(dep: &cargo_metadata::Dependency, pkg: &Pkg) -> bool {
+impl std::error::Error for Cancelled {}
+
+/// Like [`MetadataCommand::exec`], but it can be cancelled, and cargo's stderr
+/// is forwarded line by line as [`LoadStage::CargoOutput`]
+fn exec_metadata_cmd(
+    cmd: &MetadataCommand,
+    sender: &LoadSend,
+    cancel: &AtomicBool,
+) -> anyhow::Result<Metadata> {
+    let mut child = cmd
+        .cargo_command()
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run cargo")?;
+    let mut stdout = child.stdout.take().context("Missing stdout")?;
+    let stderr = child.stderr.take().context("Missing stderr")?;
+    let stdout_thread = std::thread::spawn(move || {
+        let mut out = String::new();
+        stdout.read_to_string(&mut out).map(|_| out)
+    });
+    let stderr_sender = sender.clone();
+    let stderr_thread = std::thread::spawn(move || {
+        let mut out = String::new();
+        for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+            // The receiver might be gone already, but we still want to collect the output
+            let _ = stderr_sender.send(LoadStage::CargoOutput(line.clone()));
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out
+    });
+    let status = loop {
+        if cancel.load(Ordering::Relaxed) {
+            child.kill()?;
+            child.wait()?;
+            return Err(Cancelled.into());
+        }
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    let stderr = stderr_thread.join().unwrap_or_default();
+    if !status.success() {
+        return Err(CargoError { status, stderr }.into());
+    }
+    let stdout = stdout_thread.join().map_err(|_| anyhow::anyhow!("Stdout reader panicked"))??;
+    let json = stdout
+        .lines()
+        .find(|line| line.starts_with('{'))
+        .context("cargo metadata didn't output any json")?;
+    Ok(MetadataCommand::parse(json)?)
+}
+
+/// `path` can either be a project directory or a manifest file
+fn manifest_path(path: &Path) -> PathBuf {
+    if path.is_file() {
+        path.to_owned()
+    } else {
+        path.join("Cargo.toml")
+    }
+}
+
+/// Read saved `cargo metadata` output. A path of `-` reads from stdin.
+fn read_metadata_json(path: &Path) -> anyhow::Result<Metadata> {
+    let data = if path == Path::new("-") {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+    };
+    MetadataCommand::parse(data).context("Failed to parse metadata json")
+}
+
+impl Pkg {
+    /// Find the package that `dep` (one of this package's declared dependencies) resolved to
+    pub fn resolved_dep<'a>(&self, dep: &Dependency, packages: &'a PkgSlotMap) -> Option<&'a Pkg> {
+        let mut candidates = self
+            .dependencies
+            .iter()
+            .filter(|link| link.kinds.iter().any(|kind| kind.matches_dep(dep)))
+            .map(|link| &packages[link.pkg_key])
+            .filter(|pkg| pkg.cm_pkg.name == dep.name);
+        let first = candidates.next()?;
+        // The same package can be depended on multiple times under different names,
+        // so prefer the one whose version actually matches the requirement.
+        if dep_matches_pkg(dep, first) {
+            return Some(first);
+        }
+        Some(candidates.find(|pkg| dep_matches_pkg(dep, pkg)).unwrap_or(first))
+    }
+}
+
+fn dep_matches_pkg(dep: &Dependency, pkg: &Pkg) -> bool {
     pkg.cm_pkg.name == dep.name && dep.req.matches(&pkg.cm_pkg.version)
 }
 
-// When I made this I didn't realize `cargo_metadata` supplied this information through `resolve`,
-// but I don't feel like rewriting it right now.
-fn gen_dep_graph_info(pkgs: &mut PkgSlotMap) {
+fn gen_dep_graph_info(
+    pkgs: &mut PkgSlotMap,
+    resolve: &Resolve,
+    pkgid_key_mappings: &HashMap<PackageId, PkgKey>,
+    sender: &LoadSend,
+) -> anyhow::Result<()> {
+    let total = resolve.nodes.len();
+    for (i, node) in resolve.nodes.iter().enumerate() {
+        report_progress(sender, i, total)?;
+        let key = pkgid_key_mappings[&node.id];
+        for node_dep in &node.deps {
+            let dep_key = pkgid_key_mappings[&node_dep.pkg];
+            let kinds: Vec<LinkKind> = node_dep
+                .dep_kinds
+                .iter()
+                .map(|info| LinkKind {
+                    kind: info.kind,
+                    target: info.target.clone(),
+                })
+                .collect();
+            add_link(pkgs, key, dep_key, kinds);
+        }
+    }
+    finish_progress(sender, total)
+}
+
+fn gen_dep_graph_info_unresolved(pkgs: &mut PkgSlotMap, sender: &LoadSend) -> anyhow::Result<()> {
+    let mut name_map: HashMap<String, Vec<PkgKey>> = HashMap::new();
+    for (key, pkg) in pkgs.iter() {
+        name_map.entry(pkg.cm_pkg.name.clone()).or_default().push(key);
+    }
     let keys: Vec<PkgKey> = pkgs.keys().collect();
-    for a in &keys {
-        for b in &keys {
-            // Don't do anything for self
-            if a == b {
+    let total = keys.len();
+    for (i, key) in keys.into_iter().enumerate() {
+        report_progress(sender, i, total)?;
+        let mut links: Vec<(PkgKey, LinkKind)> = Vec::new();
+        for dep in &pkgs[key].cm_pkg.dependencies {
+            let Some(candidates) = name_map.get(&dep.name) else {
                 continue;
-            }
-            let [pkg_a, pkg_b] = pkgs.get_disjoint_mut([*a, *b]).unwrap();
-            for dep in &pkg_a.cm_pkg.dependencies {
-                if dep_matches_pkg(dep, pkg_b) {
-                    pkg_a.dependencies.push(DepLink {
-                        pkg_key: *b,
-                        kind: dep.kind,
-                        target: dep.target.clone(),
-                    });
-                    pkg_b.dependents.push(DepLink {
-                        pkg_key: *a,
-                        kind: dep.kind,
-                        target: dep.target.clone(),
-                    });
+            };
+            for &dep_key in candidates {
+                if dep_key != key && dep_matches_pkg(dep, &pkgs[dep_key]) {
+                    links.push((
+                        dep_key,
+                        LinkKind {
+                            kind: dep.kind,
+                            target: dep.target.clone(),
+                        },
+                    ));
                 }
             }
         }
+        for (dep_key, kind) in links {
+            add_link(pkgs, key, dep_key, vec![kind]);
+        }
     }
-    for pkg in pkgs.values_mut() {
-        pkg.dependencies.dedup_by_key(|link| link.pkg_key);
-        pkg.dependents.dedup_by_key(|link| link.pkg_key);
+    finish_progress(sender, total)
+}
+
+/// Add a dependency edge from `from` to `to`, merging it with an existing edge, if any
+fn add_link(pkgs: &mut PkgSlotMap, from: PkgKey, to: PkgKey, kinds: Vec<LinkKind>) {
+    merge_link(&mut pkgs[from].dependencies, to, &kinds);
+    merge_link(&mut pkgs[to].dependents, from, &kinds);
+}
+
+fn merge_link(links: &mut Vec<DepLink>, pkg_key: PkgKey, kinds: &[LinkKind]) {
+    match links.iter_mut().find(|link| link.pkg_key == pkg_key) {
+        Some(link) => {
+            for kind in kinds {
+                if !link.kinds.contains(kind) {
+                    link.kinds.push(kind.clone());
+                }
+            }
+        }
+        None => links.push(DepLink {
+            pkg_key,
+            kinds: kinds.to_vec(),
+        }),
     }
 }
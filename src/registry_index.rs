@@ -0,0 +1,290 @@
+//! Versions of registry packages, read from cargo's local cache of the registry index
+//!
+//! Cargo caches the index entry of every crate it resolved under
+//! `$CARGO_HOME/registry/index/<registry>/.cache/`. Only what is in that cache is known,
+//! so the newest versions shown are as of the last time cargo updated the index.
+
+use {
+    crate::lockfile::cargo_home,
+    anyhow::{bail, Context},
+    cargo_metadata::{
+        camino::Utf8PathBuf,
+        semver::{Version, VersionReq},
+        Package,
+    },
+    std::collections::BTreeMap,
+};
+
+/// The cache file format version this understands
+const CACHE_VERSION: u8 = 3;
+
+/// A dependency of a version, as listed in the index
+pub struct IndexDep {
+    /// Name of the package, even if the dependency is renamed
+    pub name: String,
+    pub req: String,
+    /// `None` for normal dependencies, otherwise `dev` or `build`
+    pub kind: Option<String>,
+    pub optional: bool,
+    pub target: Option<String>,
+}
+
+/// A published version of a package
+pub struct IndexVersion {
+    pub version: Version,
+    pub yanked: bool,
+    pub features: BTreeMap<String, Vec<String>>,
+    pub deps: Vec<IndexDep>,
+    pub rust_version: Option<String>,
+}
+
+/// Every cached version of a package, oldest first
+pub struct IndexEntry {
+    pub versions: Vec<IndexVersion>,
+}
+
+impl IndexEntry {
+    /// Read the cached index entry of `pkg`. `None` if it's not from a registry,
+    /// or the registry's cache doesn't have it.
+    pub fn load(pkg: &Package) -> anyhow::Result<Option<Self>> {
+        let Some(path) = cache_path(pkg) else {
+            return Ok(None);
+        };
+        let data = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {path}")),
+        };
+        Self::parse(&data).with_context(|| format!("Failed to parse {path}")).map(Some)
+    }
+    /// The cache file is a header, followed by pairs of null-terminated version and JSON
+    fn parse(data: &[u8]) -> anyhow::Result<Self> {
+        #[derive(serde::Deserialize)]
+        struct Line {
+            vers: Version,
+            #[serde(default)]
+            deps: Vec<Dep>,
+            #[serde(default)]
+            features: BTreeMap<String, Vec<String>>,
+            /// Features using newer syntax, kept apart so older cargo versions can ignore them
+            #[serde(default)]
+            features2: BTreeMap<String, Vec<String>>,
+            #[serde(default)]
+            yanked: bool,
+            rust_version: Option<String>,
+        }
+        #[derive(serde::Deserialize)]
+        struct Dep {
+            name: String,
+            req: String,
+            #[serde(default)]
+            optional: bool,
+            target: Option<String>,
+            kind: Option<String>,
+            package: Option<String>,
+        }
+        let Some((&version, rest)) = data.split_first() else {
+            bail!("Empty cache file");
+        };
+        if version != CACHE_VERSION {
+            bail!("Unsupported cache version {version}");
+        }
+        // Index format version (4 bytes), then the registry's version of the entry
+        let mut parts = rest.get(4..).context("Truncated header")?.split(|b| *b == 0).skip(1);
+        let mut versions = Vec::new();
+        while let (Some(_), Some(json)) = (parts.next(), parts.next()) {
+            let line: Line = serde_json::from_slice(json)?;
+            let mut features = line.features;
+            features.extend(line.features2);
+            versions.push(IndexVersion {
+                version: line.vers,
+                yanked: line.yanked,
+                features,
+                deps: line
+                    .deps
+                    .into_iter()
+                    .map(|dep| IndexDep {
+                        name: dep.package.unwrap_or(dep.name),
+                        req: dep.req,
+                        kind: dep.kind.filter(|kind| kind != "normal"),
+                        optional: dep.optional,
+                        target: dep.target,
+                    })
+                    .collect(),
+                rust_version: line.rust_version,
+            });
+        }
+        versions.sort_by(|a, b| a.version.cmp(&b.version));
+        Ok(Self { versions })
+    }
+    pub fn get(&self, version: &Version) -> Option<&IndexVersion> {
+        self.versions.iter().find(|ver| ver.version == *version)
+    }
+    /// The newest version that isn't yanked. Pre-releases only count if `current` is one.
+    pub fn newest(&self, current: &Version) -> Option<&Version> {
+        self.versions
+            .iter()
+            .rev()
+            .filter(|ver| !ver.yanked && (ver.version.pre.is_empty() || !current.pre.is_empty()))
+            .map(|ver| &ver.version)
+            .next()
+    }
+    /// The newest version that isn't yanked, and that a caret requirement on `current` allows
+    pub fn newest_compatible(&self, current: &Version) -> Option<&Version> {
+        let req = VersionReq::parse(&format!("^{current}")).ok()?;
+        self.versions
+            .iter()
+            .rev()
+            .filter(|ver| !ver.yanked && req.matches(&ver.version))
+            .map(|ver| &ver.version)
+            .next()
+    }
+}
+
+/// How a package's version compares to what the registry has, see [`VersionStatus::of`]
+pub struct VersionStatus {
+    pub yanked: bool,
+    pub newest_compatible: Option<Version>,
+    pub newest: Option<Version>,
+}
+
+impl VersionStatus {
+    /// `None` if nothing is known about the package
+    pub fn of(pkg: &Package) -> anyhow::Result<Option<Self>> {
+        let Some(entry) = IndexEntry::load(pkg)? else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            yanked: entry.get(&pkg.version).is_some_and(|ver| ver.yanked),
+            newest_compatible: entry.newest_compatible(&pkg.version).cloned(),
+            newest: entry.newest(&pkg.version).cloned(),
+        }))
+    }
+    /// Whether a newer version that is compatible with the current one is available
+    pub fn update_available(&self, current: &Version) -> bool {
+        self.newest_compatible.as_ref().is_some_and(|newest| newest > current)
+    }
+    /// Whether a newer, semver incompatible version is available
+    pub fn upgrade_available(&self) -> bool {
+        self.newest.is_some() && self.newest > self.newest_compatible
+    }
+}
+
+/// The index cache file of `pkg`, if it's from a registry
+fn cache_path(pkg: &Package) -> Option<Utf8PathBuf> {
+    let source = pkg.source.as_ref()?;
+    let url = source
+        .repr
+        .strip_prefix("registry+")
+        .or_else(|| source.repr.strip_prefix("sparse+"))?;
+    // Cargo names the directory of a registry after the host, plus a hash of the url
+    let host = if source.is_crates_io() {
+        "index.crates.io"
+    } else {
+        url.split("://").nth(1)?.split(['/', ':']).next()?
+    };
+    let index_dir = cargo_home()?.join("registry/index");
+    let mut registry_dirs: Vec<Utf8PathBuf> = index_dir
+        .read_dir_utf8()
+        .ok()?
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name();
+            name.starts_with(&format!("{host}-"))
+                || (source.is_crates_io() && name.starts_with("github.com-"))
+        })
+        .map(|entry| entry.into_path())
+        .collect();
+    // The sparse index is what current cargo versions use for crates.io
+    registry_dirs.sort_by_key(|dir| !dir.as_str().contains("/index.crates.io-"));
+    let name = pkg.name.to_lowercase();
+    let rel = match name.len() {
+        1 => format!("1/{name}"),
+        2 => format!("2/{name}"),
+        3 => format!("3/{}/{name}", &name[..1]),
+        _ => format!("{}/{}/{name}", &name[..2], &name[2..4]),
+    };
+    registry_dirs
+        .into_iter()
+        .map(|dir| dir.join(".cache").join(&rel))
+        .find(|path| path.exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::IndexEntry, cargo_metadata::semver::Version};
+
+    /// A cache file with the header, and a version and JSON pair for each line
+    fn cache_file(lines: &[&str]) -> Vec<u8> {
+        let mut data = vec![super::CACHE_VERSION, 2, 0, 0, 0];
+        data.extend_from_slice(b"etag: \"abc\"\0");
+        for line in lines {
+            let json: serde_json::Value = serde_json::from_str(line).unwrap();
+            data.extend_from_slice(json["vers"].as_str().unwrap().as_bytes());
+            data.push(0);
+            data.extend_from_slice(line.as_bytes());
+            data.push(0);
+        }
+        data
+    }
+
+    fn version(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+
+    #[test]
+    fn parses_versions() {
+        let entry = IndexEntry::parse(&cache_file(&[
+            r#"{"name":"foo","vers":"1.2.0","deps":[{"name":"bar2","package":"bar","req":"^0.3","kind":"normal","optional":true},{"name":"baz","req":"^1","kind":"dev","target":"cfg(unix)"}],"features":{"std":[]},"features2":{"serde":["dep:serde"]},"rust_version":"1.70"}"#,
+            r#"{"name":"foo","vers":"1.0.0","yanked":true}"#,
+        ]))
+        .unwrap();
+        // Sorted oldest first
+        let versions: Vec<&Version> = entry.versions.iter().map(|ver| &ver.version).collect();
+        assert_eq!(versions, [&version("1.0.0"), &version("1.2.0")]);
+        assert!(entry.versions[0].yanked);
+        let newest = &entry.versions[1];
+        assert!(!newest.yanked);
+        assert_eq!(newest.rust_version.as_deref(), Some("1.70"));
+        assert_eq!(
+            newest.features.keys().collect::<Vec<_>>(),
+            ["serde", "std"],
+            "`features2` is merged into the features"
+        );
+        let (bar, baz) = (&newest.deps[0], &newest.deps[1]);
+        assert_eq!((bar.name.as_str(), bar.req.as_str()), ("bar", "^0.3"));
+        assert!(bar.optional && bar.kind.is_none() && bar.target.is_none());
+        assert_eq!(baz.kind.as_deref(), Some("dev"));
+        assert_eq!(baz.target.as_deref(), Some("cfg(unix)"));
+    }
+
+    #[test]
+    fn newest_versions() {
+        let entry = IndexEntry::parse(&cache_file(&[
+            r#"{"name":"foo","vers":"1.0.0"}"#,
+            r#"{"name":"foo","vers":"1.1.0"}"#,
+            r#"{"name":"foo","vers":"1.2.0","yanked":true}"#,
+            r#"{"name":"foo","vers":"2.0.0"}"#,
+            r#"{"name":"foo","vers":"3.0.0-beta.1"}"#,
+        ]))
+        .unwrap();
+        let current = version("1.0.0");
+        assert_eq!(entry.newest_compatible(&current), Some(&version("1.1.0")));
+        assert_eq!(entry.newest(&current), Some(&version("2.0.0")));
+        assert_eq!(
+            entry.newest(&version("3.0.0-alpha")),
+            Some(&version("3.0.0-beta.1"))
+        );
+        assert!(entry.get(&version("1.2.0")).is_some_and(|ver| ver.yanked));
+    }
+
+    #[test]
+    fn invalid_cache_files() {
+        assert!(IndexEntry::parse(&[]).is_err());
+        assert!(IndexEntry::parse(&[super::CACHE_VERSION, 2]).is_err());
+        let mut wrong_version = cache_file(&[]);
+        wrong_version[0] = super::CACHE_VERSION + 1;
+        assert!(IndexEntry::parse(&wrong_version).is_err());
+        assert!(IndexEntry::parse(&cache_file(&[r#"{"name":"foo","vers":"one"}"#])).is_err());
+    }
+}
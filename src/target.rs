@@ -0,0 +1,61 @@
+use {
+    anyhow::Context,
+    cargo_platform::{Cfg, Platform},
+    std::process::Command,
+};
+
+/// The cfg set of a target triple, used to decide which platform specific dependencies apply
+pub struct TargetCfg {
+    pub triple: String,
+    pub cfgs: Vec<Cfg>,
+}
+
+impl TargetCfg {
+    /// Query the cfg set of `triple` with `rustc --print cfg`
+    pub fn query(triple: &str) -> anyhow::Result<Self> {
+        let out = rustc_output(&["--print", "cfg", "--target", triple])?;
+        let cfgs = out
+            .lines()
+            .map(|line| line.parse::<Cfg>())
+            .collect::<Result<_, _>>()
+            .context("Failed to parse cfg output of rustc")?;
+        Ok(Self {
+            triple: triple.to_owned(),
+            cfgs,
+        })
+    }
+    pub fn matches(&self, platform: &Platform) -> bool {
+        platform.matches(&self.triple, &self.cfgs)
+    }
+}
+
+/// The target triple of the host, as reported by `rustc -vV`
+pub fn host_triple() -> anyhow::Result<String> {
+    let out = rustc_output(&["-vV"])?;
+    out.lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .map(|host| host.trim().to_owned())
+        .context("rustc didn't report a host triple")
+}
+
+/// All the target triples known to rustc
+pub fn target_list() -> anyhow::Result<Vec<String>> {
+    let out = rustc_output(&["--print", "target-list"])?;
+    Ok(out.lines().map(|line| line.trim().to_owned()).collect())
+}
+
+fn rustc_output(args: &[&str]) -> anyhow::Result<String> {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".into());
+    let out = Command::new(&rustc)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run {rustc}"))?;
+    if !out.status.success() {
+        anyhow::bail!(
+            "{rustc} {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8(out.stdout)?)
+}
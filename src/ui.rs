@@ -1,35 +1,155 @@
 use {
-    self::widgets::{badge, DepkindBadge, VersionBadge},
+    self::widgets::{
+        advisory_kind_badge, badge, license_mismatch_label, yanked_badge, DepkindBadge,
+        VersionBadge,
+    },
     crate::{
-        app::{App, LoadStage},
+        app::App,
         config::Config,
-        project::{dep_matches_pkg, Pkg, PkgKey, PkgSlotMap, Project},
+        export::{self, ExportFormat, Subgraph},
+        features::FeatureNode,
+        license_text::LicenseFiles,
+        notices::PkgDir,
+        project::{CargoError, Pkg, PkgKey, Project, ProjectDiff},
+        registry_index::IndexEntry,
         style::{Colors, Style},
+        target::TargetCfg,
     },
+    cargo_metadata::{semver::Version, PackageId},
     eframe::egui::{self, Align2},
     egui_commonmark::CommonMarkCache,
-    std::fmt::Display,
-    tab::Tab,
+    std::{
+        collections::HashMap,
+        fmt::Display,
+        path::PathBuf,
+        time::{Duration, Instant},
+    },
+    tab::{show_compared_pkg, Tab},
 };
 
+mod features;
 mod tab;
 mod widgets;
 
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+
 pub struct Gui {
     modal_payload: Option<ModalPayload>,
+    /// Navigation state of the active project
+    pub nav: NavState,
+    pub settings_window: SettingsWindow,
+    pub style: Style,
+    pub right_panel_left: f32,
+    pub cm_cache: CommonMarkCache,
+    pub show_sidebar: bool,
+    /// Target triples known to rustc. Queried when the target selector is first opened.
+    target_triples: Option<Vec<String>>,
+    /// Hide packages that aren't built for the selected target, instead of greying them out
+    pub hide_off_target: bool,
+    /// Target selected by the user, to be applied to the project at the next opportunity
+    target_request: Option<Option<String>>,
+    /// The user requested the project to be reloaded
+    pub reload_requested: bool,
+    /// Project the user requested to open
+    pub open_request: Option<PathBuf>,
+    /// The projects that are open in the other (non-active) tabs
+    pub other_projects: Vec<OpenProjectInfo>,
+    /// Package (name and version) the user wants to look at in another project tab
+    goto_request: Option<(usize, String, Version)>,
+    /// Short notification message, and when it was shown
+    toast: Option<(String, Instant)>,
+    /// Cargo options of the active project. `None` if it wasn't loaded with cargo.
+    resolve_args: Option<crate::ResolveArgs>,
+    /// Cargo options the user wants the active project to be resolved with
+    resolve_request: Option<crate::ResolveArgs>,
+    /// Name for saving a new feature preset
+    preset_name: String,
+    /// Which part of the graph the export menu exports
+    export_scope: ExportScope,
+    pub advisory_db: tab::AdvisoryDbState,
+}
+
+/// Ui state that belongs to a particular project
+#[derive(Default)]
+pub struct NavState {
     /// Primarily viewed package (i.e. main view)
     pub primary_pkg: Option<PkgKey>,
     /// Secondarily viewer package (i.e. sidebar)
     pub secondary_pkg: Option<PkgKey>,
-    pub settings_window: SettingsWindow,
-    pub style: Style,
     pub tab: Tab,
-    pub right_panel_left: f32,
     pub pkg_list_filter_string: String,
     pub pkg_list_compiled_filter: Option<PkgFilter>,
     md: MdContent,
-    pub cm_cache: CommonMarkCache,
-    pub show_sidebar: bool,
+    /// How the project changed with the last reload
+    pub last_changes: Option<ProjectDiff>,
+    pub compare: tab::Comparison,
+    /// Root of the feature explorer window, if open
+    pub feature_explorer: Option<FeatureNode>,
+    /// Feature to show the "why is this enabled" trace of
+    pub feature_why: Option<FeatureNode>,
+    feature_why_trace: Option<features::WhyTrace>,
+    /// Package to show the inverse dependency tree of
+    pub inverse_pkg: Option<PkgKey>,
+    pub tree: tab::TreeState,
+    pub graph: tab::GraphState,
+    pub cost: tab::CostState,
+    pub duplicates: tab::DuplicatesState,
+    pub feature_costs: features::FeatureCosts,
+    pub licenses: tab::LicensesState,
+    /// License files of the package shown in the sidebar.
+    ///
+    /// By package id, as the sidebar also shows packages of compared projects,
+    /// whose keys refer to different packages.
+    sidebar_license_files: Option<(PackageId, LicenseFiles)>,
+    pub outdated: tab::OutdatedState,
+    /// Newest versions of the registry packages
+    pub versions: tab::VersionScan,
+    /// Index entry of the package shown in the sidebar, by package id like the license files
+    sidebar_index: Option<(PackageId, Option<IndexEntry>)>,
+}
+
+impl NavState {
+    /// Reset the navigation state for a newly opened project
+    pub fn reset_for_project(&mut self, project: &Project) {
+        *self = Self {
+            primary_pkg: project.initial_pkg(),
+            ..Self::default()
+        };
+    }
+    /// Point the package selection of `old` at the corresponding packages of `new`
+    pub fn carry_over_selection(&mut self, old: &Project, new: &Project) {
+        let remap = |key: PkgKey| old.packages.get(key).and_then(|pkg| new.corresponding_pkg(pkg));
+        self.primary_pkg = self.primary_pkg.and_then(remap).or_else(|| new.initial_pkg());
+        self.secondary_pkg = self.secondary_pkg.and_then(remap);
+        self.md.key = remap(self.md.key).unwrap_or_default();
+        let remap_feature = |node: Option<FeatureNode>| match node {
+            Some(FeatureNode::Feature { pkg, feature }) => {
+                remap(pkg).map(|pkg| FeatureNode::Feature { pkg, feature })
+            }
+            _ => None,
+        };
+        self.feature_explorer = remap_feature(self.feature_explorer.take());
+        self.feature_why = remap_feature(self.feature_why.take());
+        self.feature_why_trace = None;
+        self.inverse_pkg = self.inverse_pkg.and_then(remap);
+        self.tree.remap(remap);
+        self.graph.invalidate();
+        self.cost.invalidate();
+        self.duplicates.invalidate();
+        self.licenses.invalidate();
+        self.sidebar_license_files = None;
+        self.versions.invalidate();
+        self.sidebar_index = None;
+        self.feature_costs.pkg = self.feature_costs.pkg.and_then(remap);
+    }
+}
+
+/// Info about an open project, for telling which projects contain a package
+pub struct OpenProjectInfo {
+    /// Index of the project tab
+    pub tab: usize,
+    pub title: String,
+    pub versions: HashMap<String, Vec<Version>>,
 }
 
 pub enum PkgFilter {
@@ -92,6 +212,10 @@ enum MdContentKind {
 #[derive(Default)]
 pub struct SettingsWindow {
     pub open: bool,
+    /// Cargo options of the active project, as being edited. `None` if not applicable.
+    pub resolve_draft: Option<crate::ResolveArgs>,
+    /// The user wants to reload the active project with the edited cargo options
+    pub apply_resolve: bool,
 }
 
 impl SettingsWindow {
@@ -124,13 +248,104 @@ impl SettingsWindow {
                             .text_color(style.colors.text_edit_text),
                     );
                 });
+                ui.separator();
+                ui.label("Cargo options")
+                    .on_hover_text("Options for resolving the current project");
+                match &mut self.resolve_draft {
+                    Some(draft) => {
+                        resolve_args_ui(ui, draft, style);
+                        if ui.button("Apply and reload").clicked() {
+                            self.apply_resolve = true;
+                        }
+                    }
+                    None => {
+                        ui.label("Not available for projects that weren't loaded with cargo");
+                    }
+                }
             });
     }
 }
 
+fn resolve_args_ui(ui: &mut egui::Ui, args: &mut crate::ResolveArgs, style: &Style) {
+    ui.horizontal_wrapped(|ui| {
+        ui.checkbox(&mut args.no_default_features, "No default features");
+        ui.checkbox(&mut args.all_features, "All features");
+        ui.checkbox(&mut args.no_deps, "No deps");
+    });
+    ui.horizontal_wrapped(|ui| {
+        ui.checkbox(&mut args.offline, "Offline");
+        ui.checkbox(&mut args.locked, "Locked");
+        ui.checkbox(&mut args.frozen, "Frozen");
+    });
+    egui::Grid::new("resolve_args_grid").show(ui, |ui| {
+        ui.label("Features");
+        string_list_ui(ui, "features", &mut args.features, style);
+        ui.end_row();
+        ui.label("Config").on_hover_text("`--config` values (KEY=VALUE or path)");
+        string_list_ui(ui, "config", &mut args.config, style);
+        ui.end_row();
+        ui.label("Environment").on_hover_text("Extra environment variables (KEY=VALUE)");
+        string_list_ui(ui, "env", &mut args.env, style);
+        ui.end_row();
+        ui.label("Toolchain");
+        optional_text_ui(ui, &mut args.toolchain, style);
+        ui.end_row();
+        ui.label("Cargo path");
+        let mut path = args.cargo_path.as_ref().map(|path| path.display().to_string());
+        if optional_text_ui(ui, &mut path, style) {
+            args.cargo_path = path.map(PathBuf::from);
+        }
+        ui.end_row();
+    });
+}
+
+/// Edit a list of strings, one text edit per entry. Entries are removed with their ✖ button,
+/// or by leaving them empty.
+fn string_list_ui(ui: &mut egui::Ui, id: &str, list: &mut Vec<String>, style: &Style) {
+    let mut remove = None;
+    ui.vertical(|ui| {
+        for (i, item) in list.iter_mut().enumerate() {
+            let text_id = ui.make_persistent_id((id, i));
+            ui.horizontal(|ui| {
+                let re = ui.add(
+                    egui::TextEdit::singleline(item)
+                        .id(text_id)
+                        .text_color(style.colors.text_edit_text),
+                );
+                if ui.small_button("✖").on_hover_text("Remove").clicked()
+                    || (re.lost_focus() && item.trim().is_empty())
+                {
+                    remove = Some(i);
+                }
+            });
+        }
+        if ui.small_button("➕").clicked() {
+            list.push(String::new());
+            let new_id = ui.make_persistent_id((id, list.len() - 1));
+            ui.memory_mut(|mem| mem.request_focus(new_id));
+        }
+    });
+    if let Some(i) = remove {
+        list.remove(i);
+    }
+}
+
+/// Edit an optional string, where an empty string means `None`. Returns whether it changed.
+fn optional_text_ui(ui: &mut egui::Ui, value: &mut Option<String>, style: &Style) -> bool {
+    let mut text = value.clone().unwrap_or_default();
+    let changed = ui
+        .add(egui::TextEdit::singleline(&mut text).text_color(style.colors.text_edit_text))
+        .changed();
+    if changed {
+        *value = (!text.trim().is_empty()).then_some(text);
+    }
+    changed
+}
+
 struct ModalPayload {
     title: String,
     msg: String,
+    details: Option<String>,
 }
 
 impl Gui {
@@ -139,25 +354,78 @@ impl Gui {
         crate::style::apply_style(egui_ctx, style.clone());
         Self {
             modal_payload: None,
-            secondary_pkg: None,
-            primary_pkg: None,
+            nav: NavState::default(),
             settings_window: SettingsWindow::default(),
             style,
-            tab: Tab::default(),
             // Arbitrary value: Can't be 0., that causes a panic in egui.
             // Also can't call `Context::available_width` at this point, also causes panic.
             right_panel_left: 100.,
-            pkg_list_filter_string: String::new(),
-            pkg_list_compiled_filter: None,
-            md: MdContent::default(),
             cm_cache: CommonMarkCache::default(),
             show_sidebar: true,
+            target_triples: None,
+            hide_off_target: false,
+            target_request: None,
+            reload_requested: false,
+            open_request: None,
+            other_projects: Vec::new(),
+            goto_request: None,
+            toast: None,
+            resolve_args: None,
+            resolve_request: None,
+            preset_name: String::new(),
+            export_scope: ExportScope::Whole,
+            advisory_db: tab::AdvisoryDbState::default(),
         }
     }
+    pub fn show_toast(&mut self, msg: impl Into<String>) {
+        self.toast = Some((msg.into(), Instant::now()));
+    }
+    /// Show the current toast, or `loading_msg` while a project is being reloaded.
+    ///
+    /// Returns whether the user wants to cancel the reload.
+    fn toast_ui(&mut self, ctx: &egui::Context, loading_msg: Option<&str>) -> bool {
+        if self.toast.as_ref().is_some_and(|(_, shown)| shown.elapsed() > TOAST_DURATION) {
+            self.toast = None;
+        }
+        if loading_msg.is_none() && self.toast.is_none() {
+            return false;
+        }
+        let mut cancel = false;
+        egui::Area::new("toast".into())
+            .anchor(Align2::RIGHT_BOTTOM, egui::vec2(-16.0, -16.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| match loading_msg {
+                    Some(msg) => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label(msg);
+                            cancel = ui.button("Cancel").clicked();
+                        });
+                    }
+                    None => {
+                        if let Some((msg, _)) = &self.toast {
+                            ui.label(msg);
+                        }
+                    }
+                });
+            });
+        ctx.request_repaint_after(TOAST_DURATION);
+        cancel
+    }
     fn set_modal(&mut self, title: impl Display, msg: impl Display) {
         self.modal_payload = Some(ModalPayload {
             title: title.to_string(),
             msg: msg.to_string(),
+            details: None,
+        });
+    }
+    /// Like [`Self::set_modal`], but with additional (possibly long) details, like process output
+    fn set_modal_with_details(&mut self, title: impl Display, msg: impl Display, details: String) {
+        self.modal_payload = Some(ModalPayload {
+            title: title.to_string(),
+            msg: msg.to_string(),
+            details: Some(details),
         });
     }
 }
@@ -178,6 +446,20 @@ pub fn do_ui(app: &mut App, ctx: &egui::Context) {
                     ui.heading(&payload.title);
                     ui.separator();
                     ui.label(&payload.msg);
+                    if let Some(details) = &payload.details {
+                        ui.separator();
+                        egui::ScrollArea::both().max_height(300.0).show(ui, |ui| {
+                            // Read-only, but still selectable for copying
+                            ui.add(
+                                egui::TextEdit::multiline(&mut details.as_str())
+                                    .font(egui::TextStyle::Monospace)
+                                    .desired_width(600.0),
+                            );
+                        });
+                        if ui.button("Copy").clicked() {
+                            ui.ctx().copy_text(details.clone());
+                        }
+                    }
                     ui.separator();
                     if ui.button("Ok").clicked() || enter || esc {
                         close = true;
@@ -189,73 +471,348 @@ pub fn do_ui(app: &mut App, ctx: &egui::Context) {
             app.gui.modal_payload = None;
         }
     }
-    match &mut app.project {
+    let dropped = ctx.input(|inp| inp.raw.dropped_files.iter().find_map(|file| file.path.clone()));
+    let dropped = dropped.filter(|path| {
+        let openable = path.is_dir() || path.file_name().is_some_and(|name| name == "Cargo.toml");
+        if !openable {
+            app.gui.show_toast("Only project folders and Cargo.toml files can be opened");
+        }
+        openable
+    });
+    if let Some(path) = app.gui.open_request.take().or(dropped) {
+        app.open_project(path);
+    }
+    for idx in 0..app.tabs.len() {
+        poll_load(app, idx, ctx);
+    }
+    let reload_active = std::mem::take(&mut app.gui.reload_requested)
+        || ctx.input(|inp| inp.key_pressed(egui::Key::F5));
+    for (idx, tab) in app.tabs.iter_mut().enumerate() {
+        if tab.project.is_some() && tab.load.is_none() {
+            let files_changed = tab.watcher.as_mut().is_some_and(|watcher| watcher.poll());
+            let reload_requested = reload_active && idx == app.active_tab;
+            if reload_requested && !tab.can_reload() {
+                app.gui.show_toast("Metadata read from stdin can't be reloaded");
+            } else if files_changed || reload_requested {
+                tab.reload();
+            }
+        }
+    }
+    if let Some((idx, name, version)) = app.gui.goto_request.take() {
+        app.switch_tab(idx);
+        if let Some(project) = &app.tabs[idx].project {
+            app.gui.nav.secondary_pkg = project.find_pkg(&name, &version);
+            app.gui.show_sidebar = true;
+        }
+    }
+    sync_resolve_args(app);
+    run_comparison(app, ctx);
+    run_feature_costs(app, ctx);
+    if app.tabs.len() > 1 || app.tabs[0].project.is_some() {
+        project_tabs_ui(app, ctx);
+    }
+    let tab = &mut app.tabs[app.active_tab];
+    match &mut tab.project {
         Some(proj) => project_ui(proj, ctx, &mut app.gui, &mut app.config),
         None => {
-            egui::CentralPanel::default().show(ctx, |ui| match &mut app.load {
+            egui::CentralPanel::default().show(ctx, |ui| match &tab.load {
                 Some(load) => {
                     ui.label(format!("Loading project at \"{}\"...", load.path.display()));
-                    ui.label(&load.msg);
-                    match load.recv.try_recv() {
-                        Ok(stage) => match stage {
-                            LoadStage::Finished(proj) => {
-                                app.gui.primary_pkg = proj.root;
-                                app.project = Some(proj);
-                                app.load = None;
-                            }
-                            LoadStage::Error(err) => {
-                                app.gui.set_modal("Error loading project", err);
-                                app.load = None;
-                            }
-                            LoadStage::MetadataQuery => {
-                                load.msg = "Querying metadata...".into();
-                            }
-                            LoadStage::PkgInfoCollect => {
-                                load.msg = "Collecting package info...".into()
-                            }
-                            LoadStage::Resolve => load.msg = "Resolving dependencies...".into(),
-                            LoadStage::GenDepGraph => {
-                                load.msg = "Generating dependency graph...".into()
-                            }
-                        },
-                        Err(e) => match e {
-                            std::sync::mpsc::TryRecvError::Empty => {
-                                ui.spinner();
-                            }
-                            std::sync::mpsc::TryRecvError::Disconnected => {
-                                ui.label("Error loading: Channel disconnected.");
-                            }
-                        },
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(load.status());
+                    });
+                    if !load.cargo_output.is_empty() {
+                        ui.label(egui::RichText::new(&load.cargo_output).monospace());
                     }
-                    ctx.request_repaint();
+                    if ui.button("Cancel").clicked() {
+                        load.cancel();
+                        tab.load = None;
+                    }
+                }
+                None => welcome_ui(ui, &mut app.gui, &mut app.config),
+            });
+        }
+    }
+    // Loading on top of an existing project (a reload, or opening another project in its place)
+    // gets a less intrusive indicator
+    if tab.project.is_some() {
+        if let Some(load) = &tab.load {
+            if app.gui.toast_ui(ctx, Some(&load.status())) {
+                tab.abandon_load();
+            }
+            return;
+        }
+    }
+    app.gui.toast_ui(ctx, None);
+}
+
+/// Apply requested cargo options to the active tab, and make its current ones available to the ui
+fn sync_resolve_args(app: &mut App) {
+    let gui = &mut app.gui;
+    let tab = &mut app.tabs[app.active_tab];
+    let args = tab
+        .last_load
+        .as_mut()
+        .map(|(_, args)| args)
+        .filter(|args| args.metadata_json.is_none() && !args.lockfile_only);
+    let Some(args) = args else {
+        gui.resolve_args = None;
+        gui.resolve_request = None;
+        gui.settings_window.resolve_draft = None;
+        return;
+    };
+    if std::mem::take(&mut gui.settings_window.apply_resolve) {
+        gui.resolve_request = gui.settings_window.resolve_draft.clone();
+    }
+    let requested = gui.resolve_request.take();
+    let reload = requested.is_some();
+    if let Some(request) = requested {
+        args.resolve = request;
+        gui.settings_window.resolve_draft = None;
+    }
+    if !gui.settings_window.open {
+        gui.settings_window.resolve_draft = None;
+    } else if gui.settings_window.resolve_draft.is_none() {
+        gui.settings_window.resolve_draft = Some(args.resolve.clone());
+    }
+    gui.resolve_args = Some(args.resolve.clone());
+    if reload {
+        tab.reload();
+    }
+}
+
+fn run_comparison(app: &mut App, ctx: &egui::Context) {
+    let compare = &mut app.gui.nav.compare;
+    if std::mem::take(&mut compare.run_requested) {
+        if let (Some((path, args)), Some(resolve)) =
+            (&app.tabs[app.active_tab].last_load, &app.gui.resolve_args)
+        {
+            let args = crate::Args {
+                resolve: resolve.clone(),
+                ..args.clone()
+            };
+            compare.start(path.clone(), args);
+        }
+    }
+    if compare.poll() {
+        ctx.request_repaint();
+    }
+}
+
+fn run_feature_costs(app: &mut App, ctx: &egui::Context) {
+    let costs = &mut app.gui.nav.feature_costs;
+    if let Some(key) = costs.run_requested.take() {
+        let tab = &app.tabs[app.active_tab];
+        if let (Some((path, args)), Some(resolve), Some(pkg)) = (
+            &tab.last_load,
+            &app.gui.resolve_args,
+            tab.project.as_ref().and_then(|project| project.packages.get(key)),
+        ) {
+            let args = crate::Args {
+                resolve: resolve.clone(),
+                ..args.clone()
+            };
+            costs.start(path.clone(), args, pkg);
+        }
+    }
+    if costs.poll() {
+        ctx.request_repaint();
+    }
+}
+
+fn project_tabs_ui(app: &mut App, ctx: &egui::Context) {
+    enum Action {
+        Switch(usize),
+        Close(usize),
+        New,
+    }
+    let mut action = None;
+    egui::TopBottomPanel::top("project_tabs").show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            for (idx, tab) in app.tabs.iter().enumerate() {
+                let mut text = egui::RichText::new(tab.title());
+                if idx == app.active_tab {
+                    text = text.strong();
+                }
+                let re = ui.selectable_label(idx == app.active_tab, text);
+                if let Some((path, _)) = &tab.last_load {
+                    re.clone().on_hover_text(path.display().to_string());
+                }
+                if re.clicked() {
+                    action = Some(Action::Switch(idx));
+                }
+                if ui.small_button("🗙").on_hover_text("Close").clicked() {
+                    action = Some(Action::Close(idx));
+                }
+                ui.separator();
+            }
+            if ui.button("➕").on_hover_text("New tab").clicked() {
+                action = Some(Action::New);
+            }
+        });
+    });
+    match action {
+        Some(Action::Switch(idx)) => app.switch_tab(idx),
+        Some(Action::Close(idx)) => app.close_tab(idx),
+        Some(Action::New) => app.new_tab(),
+        None => {}
+    }
+}
+
+fn welcome_ui(ui: &mut egui::Ui, gui: &mut Gui, cfg: &mut Config) {
+    ui.vertical_centered(|ui| {
+        ui.heading("No project loaded");
+        ui.label("Open a project, or drop a project folder or Cargo.toml onto the window.");
+        ui.horizontal(|ui| {
+            open_project_buttons_ui(ui, gui);
+        });
+    });
+    if cfg.recent_projects.is_empty() {
+        return;
+    }
+    ui.separator();
+    cheader("Recent projects", &gui.style).default_open(true).show(ui, |ui| {
+        let mut remove = None;
+        for (i, path) in cfg.recent_projects.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.button("🗑").on_hover_text("Remove from list").clicked() {
+                    remove = Some(i);
                 }
-                None => {
-                    ui.label("No project loaded");
+                if ui.link(path.display().to_string()).clicked() {
+                    gui.open_request = Some(path.clone());
                 }
             });
         }
+        if let Some(i) = remove {
+            cfg.recent_projects.remove(i);
+        }
+    });
+}
+
+fn open_project_buttons_ui(ui: &mut egui::Ui, gui: &mut Gui) {
+    if ui.button("🗁 Open project folder...").clicked() {
+        gui.open_request = rfd::FileDialog::new().pick_folder();
+    }
+    if ui.button("🖹 Open Cargo.toml...").clicked() {
+        gui.open_request =
+            rfd::FileDialog::new().add_filter("Cargo manifest", &["toml"]).pick_file();
+    }
+    if gui.open_request.is_some() {
+        ui.close_menu();
+    }
+}
+
+/// Process the messages of the loading thread of tab `idx`, if there is one
+fn poll_load(app: &mut App, idx: usize, ctx: &egui::Context) {
+    let tab = &mut app.tabs[idx];
+    let Some(load) = &mut tab.load else {
+        return;
+    };
+    ctx.request_repaint();
+    match load.poll() {
+        Some(Ok(proj)) => {
+            tab.load = None;
+            app.set_project(idx, proj, ctx);
+        }
+        Some(Err(err)) => {
+            tab.abandon_load();
+            match err.downcast_ref::<CargoError>() {
+                Some(cargo_err) => app.gui.set_modal_with_details(
+                    "Error loading project",
+                    cargo_err,
+                    cargo_err.stderr.clone(),
+                ),
+                None => app.gui.set_modal("Error loading project", format!("{err:#}")),
+            }
+        }
+        None => {}
     }
 }
 
 pub fn project_ui(project: &mut Project, ctx: &egui::Context, gui: &mut Gui, cfg: &mut Config) {
-    egui::CentralPanel::default().show(ctx, |ui| match gui.tab {
+    gui.advisory_db.sync(cfg, ctx);
+    if let Some(request) = gui.target_request.take() {
+        match request.map(|triple| TargetCfg::query(&triple)).transpose() {
+            Ok(target) => project.set_target(target),
+            Err(e) => gui.set_modal("Error querying target", format!("{e:#}")),
+        }
+    }
+    egui::CentralPanel::default().show(ctx, |ui| match gui.nav.tab {
         Tab::ViewSingle => tab::view_single_ui(ui, gui, project, cfg),
         Tab::PackageList => tab::package_list_ui(project, ui, gui),
         Tab::Markdown => tab::markdown_ui(ui, gui, project),
-        Tab::Licenses => tab::licenses_ui(ui, gui, project),
+        Tab::Licenses => tab::licenses_ui(ui, gui, project, cfg),
+        Tab::Workspace => tab::workspace_ui(ui, gui, project),
+        Tab::Compare => tab::compare_ui(ui, gui, project, cfg),
+        Tab::Inverse => tab::inverse_ui(ui, gui, project),
+        Tab::Tree => tab::tree_ui(ui, gui, project),
+        Tab::Graph => tab::graph_ui(ui, gui, project),
+        Tab::Duplicates => tab::duplicates_ui(ui, gui, project),
+        Tab::Cost => tab::cost_ui(ui, gui, project),
+        Tab::Advisories => tab::advisories_ui(ui, gui, project, cfg),
+        Tab::Outdated => tab::outdated_ui(ui, gui, project),
     });
-    if let (Some(key), true) = (gui.secondary_pkg, gui.show_sidebar) {
+    if let (Some(key), true) = (gui.nav.secondary_pkg, gui.show_sidebar) {
         let re = egui::SidePanel::right("right_panel")
             .max_width(ctx.available_rect().width() / 2.5)
             .show(ctx, |ui| {
                 let pkg = &project.packages[key];
-                pkg_info_ui(ui, pkg, &project.packages, gui, cfg);
+                pkg_info_ui(ui, pkg, project, gui, cfg, false);
             });
         gui.right_panel_left = re.response.rect.left();
+    } else if let (Some((side, key)), true) = (gui.nav.compare.sidebar_pkg, gui.show_sidebar) {
+        compared_pkg_sidebar_ui(ctx, project, gui, cfg, side, key);
     } else {
         gui.right_panel_left = ctx.available_rect().width();
     }
     gui.settings_window.ui(ctx, &mut gui.style, cfg);
+    features::feature_explorer_ui(ctx, project, gui);
+}
+
+/// Show a package of a compared project in the sidebar, see [`tab::Comparison`]
+fn compared_pkg_sidebar_ui(
+    ctx: &egui::Context,
+    project: &Project,
+    gui: &mut Gui,
+    cfg: &mut Config,
+    side: usize,
+    key: PkgKey,
+) {
+    let compare = std::mem::take(&mut gui.nav.compare);
+    let Some((compared, key)) = compare.sidebar_project().map(|(proj, _)| (proj, key)) else {
+        gui.nav.compare = compare;
+        gui.nav.compare.sidebar_pkg = None;
+        return;
+    };
+    let (primary_before, md_key_before) = (gui.nav.primary_pkg, gui.nav.md.key);
+    let re = egui::SidePanel::right("right_panel")
+        .max_width(ctx.available_rect().width() / 2.5)
+        .show(ctx, |ui| {
+            // The compared project can't be resolved again, so its features can't be toggled
+            pkg_info_ui(ui, &compared.packages[key], compared, gui, cfg, true);
+        });
+    gui.right_panel_left = re.response.rect.left();
+    // Navigation in the sidebar refers to packages of the compared project.
+    // Point it at the current project where possible.
+    let to_current = |key: PkgKey| {
+        let pkg = compared.packages.get(key)?;
+        project.find_pkg(&pkg.cm_pkg.name, &pkg.cm_pkg.version)
+    };
+    if gui.nav.primary_pkg != primary_before {
+        gui.nav.primary_pkg = gui.nav.primary_pkg.and_then(to_current).or(primary_before);
+    }
+    if gui.nav.md.key != md_key_before {
+        gui.nav.md.key = to_current(gui.nav.md.key).unwrap_or_default();
+    }
+    let selected = gui.nav.secondary_pkg.take().and_then(|key| {
+        let pkg = compared.packages.get(key)?;
+        Some((pkg.cm_pkg.name.clone(), pkg.cm_pkg.version.clone()))
+    });
+    gui.nav.compare = compare;
+    if let Some((name, version)) = selected {
+        show_compared_pkg(gui, project, side, &name, &version);
+    }
 }
 
 fn markdown_tab_label(kind: MdContentKind, pkgname: &str) -> String {
@@ -270,23 +827,26 @@ fn markdown_tab_label(kind: MdContentKind, pkgname: &str) -> String {
 fn central_top_bar(ui: &mut egui::Ui, gui: &mut Gui, project: &Project) {
     ui.horizontal(|ui| {
         ui.set_width(gui.right_panel_left - 16.0);
-        let active_pkg = gui.primary_pkg.map(|key| &project.packages[key]);
+        let active_pkg = gui.nav.primary_pkg.map(|key| &project.packages[key]);
         let tab_str_buf;
+        let inverse_str_buf;
         for (tab, tabname) in [
             (
                 Tab::ViewSingle,
                 active_pkg.map(|pkg| pkg.cm_pkg.name.as_str()).unwrap_or("Single view"),
             ),
             (Tab::PackageList, "Packages"),
+            (Tab::Tree, "Tree"),
+            (Tab::Graph, "Graph"),
             (Tab::Markdown, {
-                if gui.md.md.is_empty() {
+                if gui.nav.md.md.is_empty() {
                     "Markdown"
                 } else {
                     tab_str_buf = markdown_tab_label(
-                        gui.md.kind,
+                        gui.nav.md.kind,
                         project
                             .packages
-                            .get(gui.md.key)
+                            .get(gui.nav.md.key)
                             .map(|pkg| pkg.cm_pkg.name.as_str())
                             .unwrap_or("Unknown"),
                     );
@@ -294,15 +854,30 @@ fn central_top_bar(ui: &mut egui::Ui, gui: &mut Gui, project: &Project) {
                 }
             }),
             (Tab::Licenses, "Licenses"),
+            (Tab::Advisories, "Advisories"),
+            (Tab::Outdated, "Outdated"),
+            (Tab::Duplicates, "Duplicates"),
+            (Tab::Cost, "Cost"),
+            (Tab::Workspace, "Workspace"),
+            (Tab::Compare, "Compare"),
+            (Tab::Inverse, {
+                match gui.nav.inverse_pkg.and_then(|key| project.packages.get(key)) {
+                    Some(pkg) => {
+                        inverse_str_buf = format!("Why {}?", pkg.cm_pkg.name);
+                        &inverse_str_buf
+                    }
+                    None => "Why here?",
+                }
+            }),
         ] {
             if ui
                 .selectable_label(
-                    gui.tab == tab,
+                    gui.nav.tab == tab,
                     egui::RichText::new(tabname).color(gui.style.colors.highlighted_text),
                 )
                 .clicked()
             {
-                gui.tab = tab;
+                gui.nav.tab = tab;
             }
         }
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -314,25 +889,183 @@ fn central_top_bar(ui: &mut egui::Ui, gui: &mut Gui, project: &Project) {
             if ui.button(icon).on_hover_text(tooltip).clicked() {
                 gui.show_sidebar ^= true;
             }
+            ui.menu_button("💾", |ui| {
+                export_menu_ui(ui, gui, project);
+            })
+            .response
+            .on_hover_text("Export dependency graph");
+            ui.menu_button("📂", |ui| {
+                open_project_buttons_ui(ui, gui);
+            })
+            .response
+            .on_hover_text("Open another project");
+            if ui.button("🔄").on_hover_text("Reload project (F5)").clicked() {
+                gui.reload_requested = true;
+            }
             if ui.button(gui.style.icons.settings).on_hover_text("Settings").clicked() {
                 gui.settings_window.open ^= true;
             }
-            match project.root {
-                Some(root) => {
+            target_combo_ui(ui, gui, project);
+            if !project.warnings.is_empty() {
+                ui.menu_button(format!("⚠ {}", project.warnings.len()), |ui| {
+                    for warning in &project.warnings {
+                        ui.label(warning);
+                    }
+                })
+                .response
+                .on_hover_text("Warnings from loading the project");
+            }
+            match (project.root, project.workspace_members.as_slice()) {
+                (Some(root), [_]) => {
                     let pkg = &project.packages[root];
                     if ui.link(format!("go to root ({})", pkg.cm_pkg.name)).clicked() {
-                        gui.primary_pkg = Some(pkg.key);
-                        gui.tab = Tab::ViewSingle;
+                        gui.nav.primary_pkg = Some(pkg.key);
+                        gui.nav.tab = Tab::ViewSingle;
                     }
                 }
-                None => {
+                (_, []) => {
                     ui.add_enabled(false, egui::Link::new("root"));
                 }
+                (_, members) => member_switcher_ui(ui, gui, project, members),
             }
         });
     });
 }
 
+/// Which part of the dependency graph the export menu exports
+#[derive(Default, Clone, Copy, PartialEq)]
+enum ExportScope {
+    #[default]
+    Whole,
+    /// The selected package and its dependents
+    Ancestors,
+    /// The selected package and its dependencies
+    Subtree,
+}
+
+fn export_menu_ui(ui: &mut egui::Ui, gui: &mut Gui, project: &Project) {
+    let selected = gui
+        .nav
+        .secondary_pkg
+        .or(gui.nav.primary_pkg)
+        .filter(|key| project.packages.contains_key(*key));
+    ui.radio_value(&mut gui.export_scope, ExportScope::Whole, "Whole graph");
+    if let Some(key) = selected {
+        let name = &project.packages[key].cm_pkg.name;
+        ui.radio_value(
+            &mut gui.export_scope,
+            ExportScope::Subtree,
+            format!("{name} and its dependencies"),
+        );
+        ui.radio_value(
+            &mut gui.export_scope,
+            ExportScope::Ancestors,
+            format!("{name} and its dependents"),
+        );
+    }
+    let subgraph = match (gui.export_scope, selected) {
+        (ExportScope::Subtree, Some(key)) => Subgraph::Subtree(key),
+        (ExportScope::Ancestors, Some(key)) => Subgraph::Ancestors(key),
+        _ => Subgraph::Whole,
+    };
+    ui.separator();
+    for format in ExportFormat::ALL {
+        ui.horizontal(|ui| {
+            if ui.button(format!("Save {}...", format.name())).clicked() {
+                ui.close_menu();
+                let path = rfd::FileDialog::new()
+                    .add_filter(format.name(), &[format.extension()])
+                    .set_file_name(format!("dependencies.{}", format.extension()))
+                    .save_file();
+                if let Some(path) = path {
+                    match std::fs::write(&path, export::export(project, subgraph, format)) {
+                        Ok(()) => gui.show_toast(format!("Exported to {}", path.display())),
+                        Err(e) => gui.set_modal("Export failed", e),
+                    }
+                }
+            }
+            if ui.button("Copy").clicked() {
+                ui.close_menu();
+                ui.ctx().copy_text(export::export(project, subgraph, format));
+                gui.show_toast(format!("Copied {} to clipboard", format.name()));
+            }
+        });
+    }
+}
+
+fn member_switcher_ui(ui: &mut egui::Ui, gui: &mut Gui, project: &Project, members: &[PkgKey]) {
+    let selected = gui
+        .nav
+        .primary_pkg
+        .filter(|key| members.contains(key))
+        .map_or("<member>", |key| project.packages[key].cm_pkg.name.as_str());
+    egui::ComboBox::new("member_combo", "")
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            for &key in members {
+                let name = &project.packages[key].cm_pkg.name;
+                let label = if project.root == Some(key) {
+                    format!("{name} (root)")
+                } else {
+                    name.to_string()
+                };
+                if ui.selectable_label(gui.nav.primary_pkg == Some(key), label).clicked() {
+                    gui.nav.primary_pkg = Some(key);
+                    gui.nav.tab = Tab::ViewSingle;
+                }
+            }
+        })
+        .response
+        .on_hover_text("Go to workspace member");
+}
+
+fn target_combo_ui(ui: &mut egui::Ui, gui: &mut Gui, project: &Project) {
+    let selected = project.target.as_ref().map_or("All targets", |target| &target.triple);
+    egui::ComboBox::new("target_combo", "")
+        .selected_text(selected)
+        .height(400.0)
+        .show_ui(ui, |ui| {
+            let triples =
+                gui.target_triples.get_or_insert_with(|| {
+                    match crate::target::host_triple().and_then(|host| {
+                        let mut list = crate::target::target_list()?;
+                        list.retain(|triple| *triple != host);
+                        list.insert(0, host);
+                        Ok(list)
+                    }) {
+                        Ok(list) => list,
+                        Err(e) => {
+                            eprintln!("Failed to query target list: {e:#}");
+                            Vec::new()
+                        }
+                    }
+                });
+            let mut request = None;
+            if ui.selectable_label(project.target.is_none(), "All targets").clicked() {
+                request = Some(None);
+            }
+            for triple in triples.iter() {
+                if ui.selectable_label(selected == triple, triple).clicked() {
+                    request = Some(Some(triple.clone()));
+                }
+            }
+            if request.is_some() {
+                gui.target_request = request;
+            }
+        })
+        .response
+        .on_hover_text("Target platform to filter dependencies for");
+    ui.checkbox(&mut gui.hide_off_target, "Hide")
+        .on_hover_text("Hide packages that aren't built for the selected target");
+}
+
+/// Grey out the contents of `ui` if they don't apply to the selected target
+fn dim_off_target(ui: &mut egui::Ui, on_target: bool) {
+    if !on_target {
+        ui.multiply_opacity(0.4);
+    }
+}
+
 fn additional_dep_info_ui(dep: &cargo_metadata::Dependency, ui: &mut egui::Ui) {
     if let Some(source) = &dep.source {
         if let Some(suffix) = source.strip_prefix("git+") {
@@ -353,35 +1086,52 @@ fn additional_dep_info_ui(dep: &cargo_metadata::Dependency, ui: &mut egui::Ui) {
     }
 }
 
-fn pkg_info_ui(ui: &mut egui::Ui, pkg: &Pkg, packages: &PkgSlotMap, gui: &mut Gui, cfg: &Config) {
+/// Information about `pkg`. A `read_only` package is not of the open project, so the
+/// controls that re-resolve the project or refer to its packages are left out.
+fn pkg_info_ui(
+    ui: &mut egui::Ui,
+    pkg: &Pkg,
+    project: &Project,
+    gui: &mut Gui,
+    cfg: &mut Config,
+    read_only: bool,
+) {
     ui.horizontal(|ui| {
         ui.label(
             egui::RichText::new(&pkg.cm_pkg.name)
                 .heading()
                 .color(gui.style.colors.highlighted_text),
         );
-        if gui.primary_pkg != Some(pkg.key)
+        if gui.nav.primary_pkg != Some(pkg.key)
             && ui.button("👁").on_hover_text("Open in main view").clicked()
         {
-            gui.primary_pkg = Some(pkg.key);
-            gui.secondary_pkg = None;
-            gui.tab = Tab::ViewSingle;
+            gui.nav.primary_pkg = Some(pkg.key);
+            gui.nav.secondary_pkg = None;
+            gui.nav.tab = Tab::ViewSingle;
         }
         if ui.button("🖹").on_hover_text("View Cargo.toml.orig").clicked() {
             match std::fs::read_to_string(pkg.manifest_dir.join("Cargo.toml.orig")) {
                 Ok(data) => {
-                    gui.md = MdContent::new(
+                    gui.nav.md = MdContent::new(
                         format!("```toml\n{data}\n```"),
                         MdContentKind::CargoToml,
                         pkg.key,
                     );
-                    gui.tab = Tab::Markdown;
+                    gui.nav.tab = Tab::Markdown;
                 }
                 Err(e) => {
                     gui.set_modal("Error", format!("Could not open Cargo.toml.orig: {e}"));
                 }
             }
         }
+        if ui
+            .button("Why here?")
+            .on_hover_text("Show the paths leading to this package")
+            .clicked()
+        {
+            gui.nav.inverse_pkg = Some(pkg.key);
+            gui.nav.tab = Tab::Inverse;
+        }
         if ui
             .button("🗁")
             .on_hover_text(format!("{}\nOpen directory", pkg.manifest_dir.as_str()))
@@ -470,24 +1220,24 @@ fn pkg_info_ui(ui: &mut egui::Ui, pkg: &Pkg, packages: &PkgSlotMap, gui: &mut Gu
     if let Some(path) = &pkg.readme_path {
         ui.horizontal(|ui| {
             if ui.link("Readme").clicked() {
-                gui.md = MdContent::new(
+                gui.nav.md = MdContent::new(
                     std::fs::read_to_string(path).unwrap(),
                     MdContentKind::Readme,
                     pkg.key,
                 );
-                gui.tab = Tab::Markdown;
+                gui.nav.tab = Tab::Markdown;
             }
         });
     }
     if let Some(path) = &pkg.changelog_path {
         ui.horizontal(|ui| {
             if ui.link("Changelog").clicked() {
-                gui.md = MdContent::new(
+                gui.nav.md = MdContent::new(
                     std::fs::read_to_string(path).unwrap(),
                     MdContentKind::Changelog,
                     pkg.key,
                 );
-                gui.tab = Tab::Markdown;
+                gui.nav.tab = Tab::Markdown;
             }
         });
     }
@@ -498,38 +1248,109 @@ fn pkg_info_ui(ui: &mut egui::Ui, pkg: &Pkg, packages: &PkgSlotMap, gui: &mut Gu
             None => ui.label("Unknown"),
         };
     });
+    let license_files = match &mut gui.nav.sidebar_license_files {
+        Some((id, files)) if *id == pkg.cm_pkg.id => files,
+        cache => &cache.insert((pkg.cm_pkg.id.clone(), LicenseFiles::check(&PkgDir::of(pkg)))).1,
+    };
+    license_mismatch_label(ui, license_files);
+    if let Some(db) = &gui.advisory_db.db {
+        for advisory in db.for_pkg(pkg) {
+            ui.horizontal_wrapped(|ui| {
+                advisory_kind_badge(ui, advisory.kind);
+                ui.hyperlink_to(&advisory.id, advisory.link());
+                ui.label(&advisory.title);
+            });
+        }
+    }
+    let index_entry = match &mut gui.nav.sidebar_index {
+        Some((id, entry)) if *id == pkg.cm_pkg.id => entry,
+        cache => {
+            let entry = IndexEntry::load(&pkg.cm_pkg).ok().flatten();
+            &cache.insert((pkg.cm_pkg.id.clone(), entry)).1
+        }
+    };
+    if let Some(entry) = index_entry {
+        newest_versions_ui(ui, pkg, entry);
+    }
+    other_projects_ui(ui, pkg, gui);
     ui.separator();
     egui::ScrollArea::vertical().show(ui, |ui| {
-        pkg_info_collapsibles_ui(pkg, gui, ui, packages);
+        pkg_info_collapsibles_ui(pkg, gui, ui, project, cfg, read_only);
     });
 }
 
-fn pkg_info_collapsibles_ui(pkg: &Pkg, gui: &mut Gui, ui: &mut egui::Ui, packages: &PkgSlotMap) {
-    if !pkg.cm_pkg.features.is_empty() {
-        cheader("Features", &gui.style).show(ui, |ui| {
-            egui::Grid::new("feat_grid").striped(true).show(ui, |ui| {
-                for (name, reqs) in &pkg.cm_pkg.features {
-                    let enabled = pkg.enabled_features.contains(name);
-                    if enabled {
-                        ui.label("☑").on_hover_text("enabled");
-                    } else {
-                        ui.label("☐").on_hover_text("disabled");
-                    }
-                    ui.label(name);
-                    ui.scope(|ui| {
-                        for req in reqs {
-                            ui.label(req);
-                        }
-                    });
-                    ui.end_row();
+/// Whether `pkg` is yanked, and which newer versions the registry index cache knows about
+fn newest_versions_ui(ui: &mut egui::Ui, pkg: &Pkg, entry: &IndexEntry) {
+    let current = &pkg.cm_pkg.version;
+    ui.horizontal_wrapped(|ui| {
+        if entry.get(current).is_some_and(|ver| ver.yanked) {
+            yanked_badge(ui);
+        }
+        let newest = entry.newest(current).filter(|newest| *newest > current);
+        let compatible = entry.newest_compatible(current).filter(|newest| *newest > current);
+        match (newest, compatible) {
+            (None, _) => ui.label("Newest version"),
+            (Some(newest), Some(compatible)) if newest != compatible => {
+                ui.label(format!("Newest: {newest} (compatible: {compatible})"))
+            }
+            (Some(newest), _) => ui.label(format!("Newest: {newest}")),
+        }
+        .on_hover_text("According to the local registry index cache");
+    });
+}
+
+/// Show which other open projects contain `pkg`, possibly in a different version
+fn other_projects_ui(ui: &mut egui::Ui, pkg: &Pkg, gui: &mut Gui) {
+    let mut goto = None;
+    let mut others = gui
+        .other_projects
+        .iter()
+        .filter_map(|info| Some((info, info.versions.get(&pkg.cm_pkg.name)?)))
+        .peekable();
+    if others.peek().is_none() {
+        return;
+    }
+    ui.horizontal_wrapped(|ui| {
+        ui.label("Also in");
+        for (info, versions) in others {
+            for version in versions {
+                let text = if *version == pkg.cm_pkg.version {
+                    info.title.clone()
+                } else {
+                    format!("{} ({version})", info.title)
+                };
+                if ui.link(text).on_hover_text("Show in that project").clicked() {
+                    goto = Some((info.tab, pkg.cm_pkg.name.clone(), version.clone()));
                 }
-            });
-        });
+            }
+        }
+    });
+    if goto.is_some() {
+        gui.goto_request = goto;
+    }
+}
+
+fn pkg_info_collapsibles_ui(
+    pkg: &Pkg,
+    gui: &mut Gui,
+    ui: &mut egui::Ui,
+    project: &Project,
+    cfg: &mut Config,
+    read_only: bool,
+) {
+    let packages = &project.packages;
+    if !pkg.cm_pkg.features.is_empty() {
+        features::features_ui(ui, pkg, project, gui, cfg, read_only);
     }
     if !pkg.dependents.is_empty() {
         cheader("Dependents", &gui.style).show(ui, |ui| {
             for link in &pkg.dependents {
+                let on_target = project.link_on_target(link);
+                if gui.hide_off_target && !on_target {
+                    continue;
+                }
                 ui.horizontal(|ui| {
+                    dim_off_target(ui, on_target);
                     let dpkg = &packages[link.pkg_key];
                     let re = ui.selectable_label(
                         false,
@@ -537,17 +1358,19 @@ fn pkg_info_collapsibles_ui(pkg: &Pkg, gui: &mut Gui, ui: &mut egui::Ui, package
                             .color(gui.style.colors.highlighted_text),
                     );
                     if re.clicked() {
-                        gui.secondary_pkg = Some(link.pkg_key);
+                        gui.nav.secondary_pkg = Some(link.pkg_key);
                         gui.show_sidebar = true;
                     }
                     if re.double_clicked() {
-                        gui.primary_pkg = Some(link.pkg_key);
+                        gui.nav.primary_pkg = Some(link.pkg_key);
                         gui.show_sidebar = false;
                     }
                     ui.add(VersionBadge::new(&dpkg.cm_pkg.version, &gui.style));
-                    ui.add(DepkindBadge::new(link.kind, &gui.style));
-                    if let Some(platform) = &link.target {
-                        ui.label(platform.to_string());
+                    for kind in &link.kinds {
+                        ui.add(DepkindBadge::new(kind.kind, &gui.style));
+                        if let Some(platform) = &kind.target {
+                            ui.label(platform.to_string());
+                        }
                     }
                 });
             }
@@ -555,14 +1378,30 @@ fn pkg_info_collapsibles_ui(pkg: &Pkg, gui: &mut Gui, ui: &mut egui::Ui, package
     }
     if !pkg.dependencies.is_empty() {
         cheader("Dependencies", &gui.style).show(ui, |ui| {
+            if project.lockfile_only {
+                lockfile_deps_ui(ui, pkg, project, gui);
+                return;
+            }
             egui::Grid::new("deps_grid").striped(true).show(ui, |ui| {
                 for dep in pkg.cm_pkg.dependencies.iter() {
-                    ui.add(DepkindBadge::new(dep.kind, &gui.style));
-                    if let Some(pkg) = packages.values().find(|pkg| dep_matches_pkg(dep, pkg)) {
+                    let dpkg = pkg.resolved_dep(dep, packages);
+                    let on_target = project.target.as_ref().is_none_or(|target| {
+                        dep.target.as_ref().is_none_or(|platform| target.matches(platform))
+                            && dpkg.is_none_or(|dpkg| dpkg.on_target)
+                    });
+                    if gui.hide_off_target && !on_target {
+                        continue;
+                    }
+                    ui.scope(|ui| {
+                        dim_off_target(ui, on_target);
+                        ui.add(DepkindBadge::new(dep.kind, &gui.style));
+                    });
+                    if let Some(dpkg) = dpkg {
                         ui.scope(|ui| {
+                            dim_off_target(ui, on_target);
                             let re = ui.selectable_label(
-                                gui.secondary_pkg == Some(pkg.key),
-                                egui::RichText::new(&pkg.cm_pkg.name)
+                                gui.nav.secondary_pkg == Some(dpkg.key),
+                                egui::RichText::new(&dpkg.cm_pkg.name)
                                     .color(gui.style.colors.highlighted_text)
                                     .strong(),
                             );
@@ -574,38 +1413,127 @@ fn pkg_info_collapsibles_ui(pkg: &Pkg, gui: &mut Gui, ui: &mut egui::Ui, package
                                     )
                                     .clicked()
                                 {
-                                    gui.primary_pkg = Some(pkg.key);
+                                    gui.nav.primary_pkg = Some(dpkg.key);
                                     ui.close_menu();
                                 }
                             });
                             if re.clicked() {
-                                gui.secondary_pkg = Some(pkg.key);
+                                gui.nav.secondary_pkg = Some(dpkg.key);
                                 gui.show_sidebar = true;
                             }
                             if re.double_clicked() {
-                                gui.primary_pkg = Some(pkg.key);
+                                gui.nav.primary_pkg = Some(dpkg.key);
                                 gui.show_sidebar = false;
                             }
-                            ui.add(VersionBadge::new(&pkg.cm_pkg.version, &gui.style));
+                            ui.add(VersionBadge::new(&dpkg.cm_pkg.version, &gui.style));
                             additional_dep_info_ui(dep, ui);
                         });
-                        if let Some(info) = &pkg.cm_pkg.description {
-                            ui.label(info);
+                        if let Some(info) = &dpkg.cm_pkg.description {
+                            ui.scope(|ui| {
+                                dim_off_target(ui, on_target);
+                                ui.label(info);
+                            });
                         }
                         ui.end_row();
                     } else {
                         ui.scope(|ui| {
+                            dim_off_target(ui, on_target);
                             ui.label(format!("{} {}", dep.name, dep.req));
                             additional_dep_info_ui(dep, ui);
                         });
                         ui.label(egui::RichText::new("Unresolved").italics())
-                            .on_hover_text("Couldn't find a package for this dependency.");
+                            .on_hover_text("Not part of the resolved dependency graph (e.g. a disabled optional dependency).");
                         ui.end_row();
                     }
                 }
             });
         });
     }
+    if let Some((id, Some(entry))) = &gui.nav.sidebar_index {
+        if *id == pkg.cm_pkg.id {
+            versions_ui(ui, pkg, entry, &gui.style);
+        }
+    }
+}
+
+/// The dependencies of `pkg` in a project built from the lockfile, where only the resolved
+/// packages are known
+fn lockfile_deps_ui(ui: &mut egui::Ui, pkg: &Pkg, project: &Project, gui: &mut Gui) {
+    ui.weak("Requirements and kinds are unknown, as the project was loaded from Cargo.lock");
+    for link in &pkg.dependencies {
+        let dpkg = &project.packages[link.pkg_key];
+        ui.horizontal(|ui| {
+            let re = ui.selectable_label(
+                gui.nav.secondary_pkg == Some(dpkg.key),
+                egui::RichText::new(&dpkg.cm_pkg.name)
+                    .color(gui.style.colors.highlighted_text)
+                    .strong(),
+            );
+            if re.clicked() {
+                gui.nav.secondary_pkg = Some(dpkg.key);
+                gui.show_sidebar = true;
+            }
+            if re.double_clicked() {
+                gui.nav.primary_pkg = Some(dpkg.key);
+                gui.show_sidebar = false;
+            }
+            ui.add(VersionBadge::new(&dpkg.cm_pkg.version, &gui.style));
+        });
+    }
+}
+
+/// Every version of `pkg` in the registry index cache, newest first, with their features
+/// and dependencies
+fn versions_ui(ui: &mut egui::Ui, pkg: &Pkg, entry: &IndexEntry, style: &Style) {
+    cheader(&format!("Versions ({})", entry.versions.len()), style).show(ui, |ui| {
+        for ver in entry.versions.iter().rev() {
+            let mut text = egui::RichText::new(ver.version.to_string());
+            if ver.version == pkg.cm_pkg.version {
+                text = text.strong().color(style.colors.highlighted_text);
+            }
+            if ver.yanked {
+                text = text.strikethrough();
+            }
+            egui::CollapsingHeader::new(text).id_salt(("index_version", &ver.version)).show(
+                ui,
+                |ui| {
+                    if ver.yanked {
+                        yanked_badge(ui);
+                    }
+                    if let Some(rust_version) = &ver.rust_version {
+                        ui.label(format!("Rust version: {rust_version}"));
+                    }
+                    if !ver.features.is_empty() {
+                        ui.strong("Features");
+                        for (name, enables) in &ver.features {
+                            ui.label(format!("{name} = [{}]", enables.join(", ")));
+                        }
+                    }
+                    if !ver.deps.is_empty() {
+                        ui.strong("Dependencies");
+                        egui::Grid::new(("index_deps", &ver.version)).striped(true).show(
+                            ui,
+                            |ui| {
+                                for dep in &ver.deps {
+                                    ui.label(dep.kind.as_deref().unwrap_or("normal"));
+                                    ui.label(format!("{} {}", dep.name, dep.req));
+                                    ui.horizontal(|ui| {
+                                        if dep.optional {
+                                            ui.label("optional");
+                                        }
+                                        if let Some(target) = &dep.target {
+                                            ui.label(target);
+                                        }
+                                    });
+                                    ui.end_row();
+                                }
+                            },
+                        );
+                    }
+                },
+            );
+        }
+    });
 }
 
 fn cheader(label: &str, style: &crate::style::Style) -> egui::CollapsingHeader {
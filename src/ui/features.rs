@@ -0,0 +1,721 @@
+use {
+    super::{
+        cheader,
+        widgets::{FeatureEdgeBadge, VersionBadge},
+        Gui,
+    },
+    crate::{
+        app::LoadState,
+        config::{Config, FeaturePreset},
+        cost::{downstream_ids, feature_cost, format_size, FeatureCost},
+        features::{
+            feature_edges, feature_sources, trace_feature, ChainStart, EdgeKind, FeatureNode,
+            FeatureSource,
+        },
+        project::{CargoError, Pkg, PkgKey, Project},
+        style::Style,
+        ResolveArgs,
+    },
+    anyhow::Context,
+    eframe::egui,
+    std::{path::PathBuf, sync::mpsc},
+};
+
+/// Sent by the thread resolving the feature configurations
+enum Resolved {
+    /// The configuration without features, which the features are compared against
+    Minimal,
+    Feature(FeatureCost),
+}
+
+/// What each feature of a workspace member adds to its dependencies,
+/// found by resolving the project once without features, and once with each feature alone
+#[derive(Default)]
+pub struct FeatureCosts {
+    /// The package whose features are analyzed
+    pub pkg: Option<PkgKey>,
+    /// The user wants the features of this package to be analyzed
+    pub run_requested: Option<PkgKey>,
+    load: Option<LoadState>,
+    /// The costs, computed on the loading thread as the configurations are resolved
+    results: Option<mpsc::Receiver<Resolved>>,
+    /// The features, in the order they are resolved after the minimal configuration
+    features: Vec<String>,
+    /// The configuration without features has been resolved
+    minimal_resolved: bool,
+    costs: Vec<FeatureCost>,
+    error: Option<String>,
+}
+
+impl FeatureCosts {
+    /// Resolve the project for each feature of `pkg` in the background
+    pub(crate) fn start(&mut self, path: PathBuf, args: crate::Args, pkg: &Pkg) {
+        if let Some(load) = &self.load {
+            load.cancel();
+        }
+        self.pkg = Some(pkg.key);
+        self.features = pkg.cm_pkg.features.keys().cloned().collect();
+        self.minimal_resolved = false;
+        self.costs.clear();
+        self.error = None;
+        let features = self.features.clone();
+        let (name, version) = (pkg.cm_pkg.name.clone(), pkg.cm_pkg.version.clone());
+        let (results_tx, results_rx) = mpsc::channel();
+        self.results = Some(results_rx);
+        self.load = Some(LoadState::spawn(path.clone(), move |tx, cancel| {
+            let find_root = |project: &Project| {
+                project
+                    .find_pkg(&name, &version)
+                    .context("The package is missing from the resolved project")
+            };
+            let mut args = args;
+            args.resolve.no_default_features = true;
+            args.resolve.all_features = false;
+            args.resolve.features.clear();
+            let project = Project::load_forwarding(&path, &args, tx, cancel)?;
+            let minimal = downstream_ids(&project, find_root(&project)?);
+            results_tx.send(Resolved::Minimal)?;
+            for feature in features {
+                args.resolve.features = vec![format!("{name}/{feature}")];
+                let project = Project::load_forwarding(&path, &args, tx, cancel)?;
+                let root = find_root(&project)?;
+                results_tx.send(Resolved::Feature(feature_cost(
+                    feature, &minimal, &project, root,
+                )))?;
+            }
+            Ok(())
+        }));
+    }
+    /// Collect the results of the resolves. Returns whether still loading.
+    pub(crate) fn poll(&mut self) -> bool {
+        let Some(load) = &mut self.load else {
+            return false;
+        };
+        // The loading thread only sends errors, the projects are dealt with on the thread
+        if let Some(Err(err)) = load.poll() {
+            self.error = Some(match err.downcast_ref::<CargoError>() {
+                Some(cargo_err) => format!("{cargo_err}\n{}", cargo_err.stderr),
+                None => format!("{err:#}"),
+            });
+            self.load = None;
+            self.results = None;
+            return false;
+        }
+        if let Some(results) = &self.results {
+            for result in results.try_iter() {
+                match result {
+                    Resolved::Minimal => self.minimal_resolved = true,
+                    Resolved::Feature(cost) => self.costs.push(cost),
+                }
+            }
+        }
+        if self.minimal_resolved && self.costs.len() == self.features.len() {
+            self.load = None;
+            self.results = None;
+            return false;
+        }
+        true
+    }
+}
+
+/// The features of `pkg`, which can be toggled for workspace members of projects loaded with cargo.
+///
+/// With `read_only`, they are only listed, without toggles or links to the feature explorer.
+pub(super) fn features_ui(
+    ui: &mut egui::Ui,
+    pkg: &Pkg,
+    project: &Project,
+    gui: &mut Gui,
+    cfg: &mut Config,
+    read_only: bool,
+) {
+    let args = gui
+        .resolve_args
+        .clone()
+        .filter(|_| !read_only && project.is_workspace_member(pkg.key));
+    let mut new_args = None;
+    let mut explore = None;
+    let mut why = None;
+    cheader("Features", &gui.style).show(ui, |ui| {
+        if let Some(args) = &args {
+            ui.horizontal_wrapped(|ui| {
+                let mut default = !args.no_default_features;
+                if ui
+                    .checkbox(&mut default, "Default features")
+                    .on_hover_text("Applies to all workspace members")
+                    .changed()
+                {
+                    new_args = Some(ResolveArgs {
+                        no_default_features: !default,
+                        ..args.clone()
+                    });
+                }
+                let mut all = args.all_features;
+                if ui
+                    .checkbox(&mut all, "All features")
+                    .on_hover_text("Applies to all workspace members")
+                    .changed()
+                {
+                    new_args = Some(ResolveArgs {
+                        all_features: all,
+                        ..args.clone()
+                    });
+                }
+            });
+            presets_ui(ui, project, gui, cfg, args, &mut new_args);
+        }
+        egui::Grid::new("feat_grid").striped(true).show(ui, |ui| {
+            for (name, reqs) in &pkg.cm_pkg.features {
+                let enabled = pkg.enabled_features.contains(name);
+                let icon = if enabled { "☑" } else { "☐" };
+                match &args {
+                    Some(args) => {
+                        let requested = feature_specs(args)
+                            .iter()
+                            .any(|spec| spec_selects(spec, pkg, project, name));
+                        let can_toggle = !args.all_features && (requested || !enabled);
+                        let re = ui.add_enabled(can_toggle, egui::Button::new(icon).small());
+                        let re = if args.all_features {
+                            re.on_disabled_hover_text("All features are enabled")
+                        } else {
+                            re.on_disabled_hover_text(
+                                "Enabled by default features or another feature",
+                            )
+                        };
+                        if re.on_hover_text("Toggle and resolve again").clicked() {
+                            new_args = Some(toggle_feature(args, pkg, project, name, requested));
+                        }
+                    }
+                    None => {
+                        ui.label(icon).on_hover_text(if enabled { "enabled" } else { "disabled" });
+                    }
+                }
+                let node = FeatureNode::Feature {
+                    pkg: pkg.key,
+                    feature: name.clone(),
+                };
+                if read_only {
+                    ui.label(name);
+                    ui.label("");
+                } else {
+                    if ui.link(name).on_hover_text("Explore what this feature turns on").clicked() {
+                        explore = Some(node.clone());
+                    }
+                    if enabled {
+                        let showing = gui.nav.feature_why.as_ref() == Some(&node);
+                        if ui
+                            .selectable_label(showing, "why?")
+                            .on_hover_text("Show what enabled this feature")
+                            .clicked()
+                        {
+                            why = Some((!showing).then_some(node));
+                        }
+                    } else {
+                        ui.label("");
+                    }
+                }
+                ui.scope(|ui| {
+                    for req in reqs {
+                        ui.label(req);
+                    }
+                });
+                ui.end_row();
+            }
+        });
+        if let Some(FeatureNode::Feature {
+            pkg: why_pkg,
+            feature,
+        }) = &gui.nav.feature_why
+        {
+            if *why_pkg == pkg.key && !read_only {
+                let feature = feature.clone();
+                feature_why_ui(ui, project, gui, pkg, &feature);
+            }
+        }
+        if args.is_some() {
+            feature_costs_ui(ui, pkg, gui);
+            last_changes_ui(ui, project, gui);
+        }
+    });
+    if let Some(why) = why {
+        gui.nav.feature_why = why;
+    }
+    if explore.is_some() {
+        gui.nav.feature_explorer = explore;
+    }
+    if new_args.is_some() {
+        gui.resolve_request = new_args;
+        ui.ctx().request_repaint();
+    }
+}
+
+/// Table of what each feature of `pkg` adds on top of no features
+fn feature_costs_ui(ui: &mut egui::Ui, pkg: &Pkg, gui: &mut Gui) {
+    if pkg.cm_pkg.features.is_empty() {
+        return;
+    }
+    let costs = &mut gui.nav.feature_costs;
+    let current = costs.pkg == Some(pkg.key);
+    egui::CollapsingHeader::new("Feature costs")
+        .id_salt("feature_costs")
+        .show(ui, |ui| {
+            ui.horizontal(|ui| match &costs.load {
+                Some(load) if current => {
+                    ui.spinner();
+                    let step = if costs.minimal_resolved {
+                        costs.features[costs.costs.len()].clone()
+                    } else {
+                        "no features".to_owned()
+                    };
+                    ui.label(format!(
+                        "Resolving {step} ({}/{}): {}",
+                        costs.costs.len() + usize::from(costs.minimal_resolved),
+                        costs.features.len() + 1,
+                        load.status()
+                    ));
+                    if ui.button("Cancel").clicked() {
+                        load.cancel();
+                        costs.load = None;
+                        costs.results = None;
+                    }
+                }
+                _ => {
+                    let label = if current { "Analyze again" } else { "Analyze" };
+                    if ui
+                        .button(label)
+                        .on_hover_text(
+                            "Resolve once without features, and once with each feature alone",
+                        )
+                        .clicked()
+                    {
+                        costs.run_requested = Some(pkg.key);
+                    }
+                }
+            });
+            if !current {
+                return;
+            }
+            if let Some(error) = &costs.error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+            let mut sorted: Vec<&FeatureCost> = costs.costs.iter().collect();
+            sorted
+                .sort_by(|a, b| b.extra.len().cmp(&a.extra.len()).then(a.feature.cmp(&b.feature)));
+            egui::Grid::new("feature_costs_grid").striped(true).show(ui, |ui| {
+                ui.strong("Feature");
+                ui.strong("Extra crates");
+                ui.strong("Build scripts");
+                ui.strong("Source size");
+                ui.end_row();
+                for cost in sorted {
+                    ui.label(&cost.feature);
+                    let re = ui.label(cost.extra.len().to_string());
+                    if !cost.extra.is_empty() {
+                        re.on_hover_ui(|ui| {
+                            for (name, version) in &cost.extra {
+                                ui.label(format!("{name} {version}"));
+                            }
+                        });
+                    }
+                    ui.label(cost.build_scripts.to_string());
+                    ui.label(format_size(cost.source_size));
+                    ui.end_row();
+                }
+            });
+        });
+}
+
+/// The individual feature specs of `args`. `--features` values can be separated by commas or spaces.
+fn feature_specs(args: &ResolveArgs) -> Vec<&str> {
+    args.features
+        .iter()
+        .flat_map(|list| list.split([',', ' ']))
+        .filter(|spec| !spec.is_empty())
+        .collect()
+}
+
+/// Whether `spec` selects `feature` of `pkg`.
+///
+/// Features without a package prefix apply to the package cargo is run for.
+fn spec_selects(spec: &str, pkg: &Pkg, project: &Project, feature: &str) -> bool {
+    match spec.split_once('/') {
+        Some((pkg_name, feat)) => pkg_name == pkg.cm_pkg.name && feat == feature,
+        None => project.root == Some(pkg.key) && spec == feature,
+    }
+}
+
+fn toggle_feature(
+    args: &ResolveArgs,
+    pkg: &Pkg,
+    project: &Project,
+    feature: &str,
+    requested: bool,
+) -> ResolveArgs {
+    let mut specs: Vec<String> = feature_specs(args).into_iter().map(str::to_owned).collect();
+    if requested {
+        specs.retain(|spec| !spec_selects(spec, pkg, project, feature));
+    } else {
+        specs.push(format!("{}/{feature}", pkg.cm_pkg.name));
+    }
+    ResolveArgs {
+        features: specs,
+        ..args.clone()
+    }
+}
+
+fn presets_ui(
+    ui: &mut egui::Ui,
+    project: &Project,
+    gui: &mut Gui,
+    cfg: &mut Config,
+    args: &ResolveArgs,
+    new_args: &mut Option<ResolveArgs>,
+) {
+    let root = project.workspace_root.clone().into_std_path_buf();
+    ui.horizontal_wrapped(|ui| {
+        ui.label("Presets");
+        let mut delete = None;
+        for (i, preset) in cfg.feature_presets.get(&root).into_iter().flatten().enumerate() {
+            let re = ui
+                .selectable_label(preset.matches(args), &preset.name)
+                .on_hover_text(preset_description(preset));
+            if re.clicked() {
+                let mut preset_args = args.clone();
+                preset.apply(&mut preset_args);
+                *new_args = Some(preset_args);
+            }
+            re.context_menu(|ui| {
+                if ui.button("Delete").clicked() {
+                    delete = Some(i);
+                    ui.close_menu();
+                }
+            });
+        }
+        if let Some(i) = delete {
+            if let Some(presets) = cfg.feature_presets.get_mut(&root) {
+                presets.remove(i);
+                if presets.is_empty() {
+                    cfg.feature_presets.remove(&root);
+                }
+            }
+        }
+        ui.add(
+            egui::TextEdit::singleline(&mut gui.preset_name)
+                .hint_text("Preset name")
+                .desired_width(100.0)
+                .text_color(gui.style.colors.text_edit_text),
+        );
+        let name = gui.preset_name.trim();
+        if ui
+            .add_enabled(!name.is_empty(), egui::Button::new("Save"))
+            .on_hover_text("Save the current feature selection as a preset")
+            .clicked()
+        {
+            cfg.save_feature_preset(root, FeaturePreset::from_args(name.to_owned(), args));
+            gui.preset_name.clear();
+        }
+    });
+}
+
+fn preset_description(preset: &FeaturePreset) -> String {
+    let mut parts = Vec::new();
+    if preset.all_features {
+        parts.push("all features".to_owned());
+    }
+    if preset.no_default_features {
+        parts.push("no default features".to_owned());
+    }
+    if !preset.features.is_empty() {
+        parts.push(preset.features.join(", "));
+    }
+    if parts.is_empty() {
+        "default features".into()
+    } else {
+        parts.join("; ")
+    }
+}
+
+/// How the packages changed with the last resolve, e.g. after toggling a feature
+fn last_changes_ui(ui: &mut egui::Ui, project: &Project, gui: &mut Gui) {
+    let Some(diff) = &gui.nav.last_changes else {
+        return;
+    };
+    let mut select = None;
+    egui::CollapsingHeader::new("Changes from last resolve").show(ui, |ui| {
+        if diff.is_empty() {
+            ui.label("No changes");
+        }
+        // The diff is of the new project against the old one
+        for (name, version) in &diff.only_in_self {
+            if ui.link(format!("+ {name} {version}")).clicked() {
+                select = project.find_pkg(name, version);
+            }
+        }
+        for (name, version) in &diff.only_in_other {
+            ui.label(format!("- {name} {version}"));
+        }
+        for change in &diff.feature_changes {
+            ui.horizontal_wrapped(|ui| {
+                if ui.link(format!("~ {} {}", change.name, change.version)).clicked() {
+                    select = project.find_pkg(&change.name, &change.version);
+                }
+                for feat in &change.only_in_self {
+                    ui.label(format!("+{feat}"));
+                }
+                for feat in &change.only_in_other {
+                    ui.label(format!("-{feat}"));
+                }
+            });
+        }
+    });
+    if select.is_some() {
+        gui.nav.secondary_pkg = select;
+        gui.show_sidebar = true;
+    }
+}
+
+/// What turned on the feature shown by [`feature_why_ui`], kept until a different one is shown
+pub struct WhyTrace {
+    node: FeatureNode,
+    start: ChainStart,
+    chain: Vec<FeatureSource>,
+    /// Everything that directly turns on the feature
+    sources: Vec<FeatureSource>,
+}
+
+/// Trace what turned on `feature` of `pkg`, back to the workspace member or declaration it started at
+fn feature_why_ui(ui: &mut egui::Ui, project: &Project, gui: &mut Gui, pkg: &Pkg, feature: &str) {
+    let node = FeatureNode::Feature {
+        pkg: pkg.key,
+        feature: feature.to_owned(),
+    };
+    if gui.nav.feature_why_trace.as_ref().is_none_or(|trace| trace.node != node) {
+        let (start, chain) = trace_feature(project, &node);
+        gui.nav.feature_why_trace = Some(WhyTrace {
+            sources: feature_sources(project, &node),
+            node: node.clone(),
+            start,
+            chain,
+        });
+    }
+    let Some(WhyTrace {
+        start,
+        chain,
+        sources,
+        ..
+    }) = &gui.nav.feature_why_trace
+    else {
+        return;
+    };
+    let mut select = None;
+    ui.group(|ui| {
+        ui.label(egui::RichText::new(format!("Why is {feature} enabled?")).strong());
+        ui.label(match start {
+            ChainStart::MemberDefault => "Default features of a workspace member",
+            ChainStart::MemberSelected => {
+                "Selected for a workspace member (--features/--all-features)"
+            }
+            ChainStart::Declaration => "Requested by a dependency of a workspace member",
+            ChainStart::Unknown => "Couldn't find what enabled it",
+        });
+        for source in chain {
+            ui.horizontal(|ui| {
+                chain_node_ui(ui, project, &gui.style, &source.from, &mut select);
+                ui.add(FeatureEdgeBadge::new(source.kind, &gui.style));
+            });
+        }
+        ui.horizontal(|ui| {
+            chain_node_ui(ui, project, &gui.style, &node, &mut select);
+        });
+        if sources.len() > 1 {
+            ui.label(format!(
+                "Also enabled by {} other sources",
+                sources.len() - 1
+            ));
+            for source in sources
+                .iter()
+                .filter(|source| chain.last().is_none_or(|last| last.from != source.from))
+            {
+                ui.horizontal(|ui| {
+                    chain_node_ui(ui, project, &gui.style, &source.from, &mut select);
+                    ui.add(FeatureEdgeBadge::new(source.kind, &gui.style));
+                });
+            }
+        }
+    });
+    // Follow the chain further by selecting the package, and showing why its feature is enabled
+    if let Some(node) = select {
+        let key = match &node {
+            FeatureNode::Feature { pkg, .. } | FeatureNode::Dep { pkg, .. } => *pkg,
+            FeatureNode::Unresolved { pkg, .. } => *pkg,
+        };
+        gui.nav.secondary_pkg = Some(key);
+        gui.show_sidebar = true;
+        if let FeatureNode::Feature { .. } = node {
+            gui.nav.feature_why = Some(node);
+        }
+    }
+}
+
+fn chain_node_ui(
+    ui: &mut egui::Ui,
+    project: &Project,
+    style: &Style,
+    node: &FeatureNode,
+    select: &mut Option<FeatureNode>,
+) {
+    let text = match node {
+        FeatureNode::Feature { pkg, feature } => {
+            format!("{}/{feature}", project.packages[*pkg].cm_pkg.name)
+        }
+        FeatureNode::Dep { pkg, name, .. } => {
+            format!("{} depends on {name}", project.packages[*pkg].cm_pkg.name)
+        }
+        FeatureNode::Unresolved { value, .. } => value.clone(),
+    };
+    if ui
+        .selectable_label(
+            false,
+            egui::RichText::new(text).color(style.colors.highlighted_text),
+        )
+        .clicked()
+    {
+        *select = Some(node.clone());
+    }
+}
+
+enum ExplorerAction {
+    Select(PkgKey),
+    Explore(FeatureNode),
+}
+
+/// State shared by the nodes of the feature explorer while drawing
+struct Explorer<'a> {
+    project: &'a Project,
+    style: &'a Style,
+    /// Nodes leading up to the current one, to avoid expanding cycles
+    path: Vec<FeatureNode>,
+    action: Option<ExplorerAction>,
+}
+
+/// Window expanding a feature into everything it turns on, recursively across packages
+pub(super) fn feature_explorer_ui(ctx: &egui::Context, project: &Project, gui: &mut Gui) {
+    let Some(root) = gui.nav.feature_explorer.clone() else {
+        return;
+    };
+    let mut open = true;
+    let mut explorer = Explorer {
+        project,
+        style: &gui.style,
+        path: Vec::new(),
+        action: None,
+    };
+    egui::Window::new("Feature explorer")
+        .open(&mut open)
+        .default_size([450.0, 500.0])
+        .show(ctx, |ui| {
+            egui::ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
+                feature_node_ui(ui, &mut explorer, &root, None, 0);
+            });
+        });
+    match explorer.action {
+        Some(ExplorerAction::Select(key)) => {
+            gui.nav.secondary_pkg = Some(key);
+            gui.show_sidebar = true;
+        }
+        Some(ExplorerAction::Explore(node)) => gui.nav.feature_explorer = Some(node),
+        None => {}
+    }
+    if !open {
+        gui.nav.feature_explorer = None;
+    }
+}
+
+/// A node of the feature explorer, expandable if it turns anything on
+fn feature_node_ui(
+    ui: &mut egui::Ui,
+    explorer: &mut Explorer,
+    node: &FeatureNode,
+    edge: Option<EdgeKind>,
+    idx: usize,
+) {
+    let repeated = explorer.path.contains(node);
+    let edges = if repeated {
+        Vec::new()
+    } else {
+        feature_edges(explorer.project, node)
+    };
+    if edges.is_empty() {
+        ui.horizontal(|ui| {
+            // Line up with the expandable siblings
+            ui.add_space(ui.spacing().indent);
+            feature_node_row_ui(ui, explorer, node, edge, repeated);
+        });
+        return;
+    }
+    let id = ui.make_persistent_id(("feature_node", idx, node));
+    let default_open = explorer.path.is_empty();
+    egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, default_open)
+        .show_header(ui, |ui| {
+            feature_node_row_ui(ui, explorer, node, edge, false);
+        })
+        .body(|ui| {
+            explorer.path.push(node.clone());
+            for (i, edge) in edges.iter().enumerate() {
+                feature_node_ui(ui, explorer, &edge.to, Some(edge.kind), i);
+            }
+            explorer.path.pop();
+        });
+}
+
+fn feature_node_row_ui(
+    ui: &mut egui::Ui,
+    explorer: &mut Explorer,
+    node: &FeatureNode,
+    edge: Option<EdgeKind>,
+    repeated: bool,
+) {
+    let (project, style) = (explorer.project, explorer.style);
+    if let Some(kind) = edge {
+        ui.add(FeatureEdgeBadge::new(kind, style));
+    }
+    match node {
+        FeatureNode::Feature { pkg, feature } => {
+            let pkg = &project.packages[*pkg];
+            if pkg.enabled_features.contains(feature) {
+                ui.label("☑").on_hover_text("enabled");
+            } else {
+                ui.label("☐").on_hover_text("disabled");
+            }
+            explorer_pkg_ui(ui, explorer, pkg);
+            if ui.link(feature).on_hover_text("Explore this feature").clicked() {
+                explorer.action = Some(ExplorerAction::Explore(node.clone()));
+            }
+        }
+        FeatureNode::Dep { name, dep_pkg, .. } => {
+            let dep_pkg = &project.packages[*dep_pkg];
+            explorer_pkg_ui(ui, explorer, dep_pkg);
+            if *name != dep_pkg.cm_pkg.name {
+                ui.label(format!("as {name}"));
+            }
+        }
+        FeatureNode::Unresolved { value, .. } => {
+            ui.label(egui::RichText::new(value).weak())
+                .on_hover_text("Not part of the resolved dependency graph");
+        }
+    }
+    if repeated {
+        ui.label("(*)").on_hover_text("Already expanded above");
+    }
+}
+
+fn explorer_pkg_ui(ui: &mut egui::Ui, explorer: &mut Explorer, pkg: &Pkg) {
+    let re = ui.selectable_label(
+        false,
+        egui::RichText::new(&pkg.cm_pkg.name).color(explorer.style.colors.highlighted_text),
+    );
+    ui.add(VersionBadge::new(&pkg.cm_pkg.version, explorer.style));
+    if re.clicked() {
+        explorer.action = Some(ExplorerAction::Select(pkg.key));
+    }
+}
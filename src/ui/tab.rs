@@ -1,11 +1,31 @@
+mod advisories;
+mod compare;
+mod cost;
+mod duplicates;
+mod graph;
+mod inverse;
 mod licenses;
 mod markdown;
+mod outdated;
 mod package_list;
+mod tree;
 mod view_single;
+mod workspace;
 
 pub(crate) use self::{
-    licenses::licenses_ui, markdown::markdown_ui, package_list::package_list_ui,
+    advisories::{advisories_ui, AdvisoryDbState},
+    compare::{compare_ui, show_pkg as show_compared_pkg, Comparison},
+    cost::{cost_ui, CostState},
+    duplicates::{duplicates_ui, DuplicatesState},
+    graph::{graph_ui, GraphState},
+    inverse::inverse_ui,
+    licenses::{licenses_ui, LicensesState},
+    markdown::markdown_ui,
+    outdated::{outdated_ui, OutdatedState, VersionScan},
+    package_list::package_list_ui,
+    tree::{tree_ui, TreeState},
     view_single::view_single_ui,
+    workspace::workspace_ui,
 };
 
 #[derive(Default, PartialEq)]
@@ -15,4 +35,13 @@ pub enum Tab {
     PackageList,
     Markdown,
     Licenses,
+    Workspace,
+    Compare,
+    Inverse,
+    Tree,
+    Graph,
+    Duplicates,
+    Cost,
+    Advisories,
+    Outdated,
 }
@@ -0,0 +1,210 @@
+use {
+    super::Tab,
+    crate::{
+        advisory::{default_db_path, Advisory, AdvisoryDb},
+        config::Config,
+        project::{PkgKey, Project},
+        ui::{central_top_bar, dim_off_target, widgets::advisory_kind_badge, Gui},
+    },
+    eframe::egui,
+    egui_commonmark::{CommonMarkCache, CommonMarkViewer},
+    std::{path::PathBuf, sync::mpsc},
+};
+
+/// The advisory database, shared by all open projects
+#[derive(Default)]
+pub struct AdvisoryDbState {
+    /// Path the database was loaded from
+    path: Option<PathBuf>,
+    /// The database being loaded on a separate thread
+    recv: Option<mpsc::Receiver<anyhow::Result<AdvisoryDb>>>,
+    pub db: Option<AdvisoryDb>,
+    error: Option<String>,
+}
+
+impl AdvisoryDbState {
+    /// Start loading the configured database unless it's already loaded, and pick up the result
+    pub fn sync(&mut self, cfg: &Config, ctx: &egui::Context) {
+        let path = cfg.advisory_db.clone().or_else(default_db_path);
+        if path != self.path {
+            (self.db, self.error, self.recv) = (None, None, None);
+            if let Some(path) = path.clone() {
+                let (tx, rx) = mpsc::channel();
+                let ctx = ctx.clone();
+                std::thread::spawn(move || {
+                    // Nobody is listening anymore if a different database was chosen meanwhile
+                    let _ = tx.send(AdvisoryDb::load(&path));
+                    ctx.request_repaint();
+                });
+                self.recv = Some(rx);
+            }
+            self.path = path;
+        }
+        if let Some(result) = self.recv.as_ref().and_then(|recv| recv.try_recv().ok()) {
+            match result {
+                Ok(db) => self.db = Some(db),
+                Err(e) => self.error = Some(format!("{e:#}")),
+            }
+            self.recv = None;
+        }
+    }
+    /// Load the database again, e.g. after pulling the latest advisories
+    fn reload(&mut self) {
+        self.path = None;
+    }
+}
+
+enum Action {
+    Select(PkgKey),
+    Why(PkgKey),
+}
+
+/// Advisories from the RustSec database that affect packages of the project
+pub(crate) fn advisories_ui(ui: &mut egui::Ui, gui: &mut Gui, project: &Project, cfg: &mut Config) {
+    central_top_bar(ui, gui, project);
+    let state = &mut gui.advisory_db;
+    ui.horizontal(|ui| {
+        ui.label("Advisory database:");
+        match &state.path {
+            Some(path) => ui.label(path.display().to_string()),
+            None => ui.label("none"),
+        };
+        if ui.button("Choose...").clicked() {
+            if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                cfg.advisory_db = Some(path);
+            }
+        }
+        if cfg.advisory_db.is_some() && ui.button("Use default").clicked() {
+            cfg.advisory_db = None;
+        }
+        if ui.button("Reload").clicked() {
+            state.reload();
+        }
+    });
+    if let Some(error) = &state.error {
+        ui.colored_label(egui::Color32::RED, error);
+    }
+    if state.recv.is_some() {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label("Reading the advisory database...");
+        });
+        return;
+    }
+    let Some(db) = &state.db else {
+        ui.label(
+            "Clone https://github.com/rustsec/advisory-db (cargo audit keeps one in \
+             ~/.cargo/advisory-db) and choose its directory to check the packages for advisories.",
+        );
+        return;
+    };
+    ui.horizontal(|ui| {
+        ui.label(format!("{} advisories", db.len()));
+        if !db.skipped.is_empty() {
+            ui.colored_label(
+                egui::Color32::YELLOW,
+                format!("{} files could not be read", db.skipped.len()),
+            )
+            .on_hover_text(db.skipped.join("\n"));
+        }
+    });
+    let mut found: Vec<(PkgKey, &Advisory)> = Vec::new();
+    for (key, pkg) in &project.packages {
+        if gui.hide_off_target && !pkg.on_target {
+            continue;
+        }
+        found.extend(db.for_pkg(pkg).into_iter().map(|advisory| (key, advisory)));
+    }
+    found.sort_by(|(key_a, a), (key_b, b)| {
+        (a.kind, &project.packages[*key_a].cm_pkg.name, &b.date).cmp(&(
+            b.kind,
+            &project.packages[*key_b].cm_pkg.name,
+            &a.date,
+        ))
+    });
+    let (informational, vulnerabilities): (Vec<_>, Vec<_>) =
+        found.into_iter().partition(|(_, advisory)| advisory.kind.is_informational());
+    ui.separator();
+    let mut action = None;
+    egui::ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
+        ui.heading(format!("Vulnerabilities ({})", vulnerabilities.len()));
+        if vulnerabilities.is_empty() {
+            ui.label("No known vulnerabilities");
+        }
+        for (key, advisory) in &vulnerabilities {
+            advisory_entry_ui(ui, *key, advisory, project, &mut gui.cm_cache, &mut action);
+        }
+        ui.separator();
+        ui.heading(format!(
+            "Unmaintained, unsound and other warnings ({})",
+            informational.len()
+        ));
+        for (key, advisory) in &informational {
+            advisory_entry_ui(ui, *key, advisory, project, &mut gui.cm_cache, &mut action);
+        }
+    });
+    match action {
+        Some(Action::Select(key)) => {
+            gui.nav.secondary_pkg = Some(key);
+            gui.show_sidebar = true;
+        }
+        Some(Action::Why(key)) => {
+            gui.nav.inverse_pkg = Some(key);
+            gui.nav.tab = Tab::Inverse;
+        }
+        None => {}
+    }
+}
+
+fn advisory_entry_ui(
+    ui: &mut egui::Ui,
+    key: PkgKey,
+    advisory: &Advisory,
+    project: &Project,
+    cm_cache: &mut CommonMarkCache,
+    action: &mut Option<Action>,
+) {
+    let pkg = &project.packages[key];
+    ui.horizontal(|ui| {
+        dim_off_target(ui, pkg.on_target);
+        advisory_kind_badge(ui, advisory.kind);
+        if ui
+            .selectable_label(false, format!("{} {}", pkg.cm_pkg.name, pkg.cm_pkg.version))
+            .clicked()
+        {
+            *action = Some(Action::Select(key));
+        }
+        ui.hyperlink_to(&advisory.id, advisory.link());
+        ui.label(&advisory.title);
+    });
+    ui.indent(("advisory", key, &advisory.id), |ui| {
+        ui.horizontal_wrapped(|ui| {
+            ui.weak(&advisory.date);
+            for alias in &advisory.aliases {
+                ui.weak(alias);
+            }
+            if let Some(url) = &advisory.url {
+                ui.hyperlink(url);
+            }
+        });
+        ui.horizontal(|ui| {
+            if advisory.patched.is_empty() {
+                ui.label("No patched version");
+            } else {
+                let patched: Vec<String> =
+                    advisory.patched.iter().map(|req| req.to_string()).collect();
+                ui.label(format!("Patched: {}", patched.join(" or ")));
+            }
+            if ui.button("Why here?").clicked() {
+                *action = Some(Action::Why(key));
+            }
+        });
+        if !advisory.description.is_empty() {
+            egui::CollapsingHeader::new("Details")
+                .id_salt(("advisory_details", key, &advisory.id))
+                .show(ui, |ui| {
+                    CommonMarkViewer::new().show(ui, cm_cache, &advisory.description);
+                });
+        }
+    });
+}
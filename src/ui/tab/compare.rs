@@ -0,0 +1,244 @@
+use {
+    crate::{
+        app::LoadState,
+        config::{Config, FeaturePreset},
+        project::{CargoError, PkgKey, Project, ProjectDiff},
+        ui::{central_top_bar, widgets::VersionBadge, Gui},
+    },
+    cargo_metadata::semver::Version,
+    eframe::egui,
+    std::path::PathBuf,
+};
+
+const SIDE_NAMES: [&str; 2] = ["A", "B"];
+
+/// Resolving a project with two different feature selections, and the differences between them
+#[derive(Default)]
+pub struct Comparison {
+    /// The feature selections to compare
+    sides: [FeaturePreset; 2],
+    /// The user wants the comparison to be (re)run
+    pub run_requested: bool,
+    load: Option<LoadState>,
+    /// The resolved project of each side, in order
+    projects: Vec<Project>,
+    /// The difference of side A to side B
+    diff: Option<ProjectDiff>,
+    error: Option<String>,
+    /// Package of a compared project shown in the sidebar, because it's not in the current project
+    pub sidebar_pkg: Option<(usize, PkgKey)>,
+}
+
+impl Comparison {
+    /// Resolve both sides of the comparison in the background
+    pub(crate) fn start(&mut self, path: PathBuf, args: crate::Args) {
+        if let Some(load) = &self.load {
+            load.cancel();
+        }
+        self.projects.clear();
+        self.diff = None;
+        self.error = None;
+        self.sidebar_pkg = None;
+        let sides = self.sides.clone();
+        self.load = Some(LoadState::spawn(path.clone(), move |tx, cancel| {
+            for side in &sides {
+                let mut args = args.clone();
+                side.apply(&mut args.resolve);
+                Project::load(&path, &args, tx.clone(), cancel)?;
+            }
+            Ok(())
+        }));
+    }
+    /// Collect the results of the resolves. Returns whether still loading.
+    pub(crate) fn poll(&mut self) -> bool {
+        let Some(load) = &mut self.load else {
+            return false;
+        };
+        while let Some(result) = load.poll() {
+            match result {
+                Ok(project) => self.projects.push(project),
+                Err(err) => {
+                    self.error = Some(match err.downcast_ref::<CargoError>() {
+                        Some(cargo_err) => format!("{cargo_err}\n{}", cargo_err.stderr),
+                        None => format!("{err:#}"),
+                    });
+                    self.load = None;
+                    return false;
+                }
+            }
+            if let [a, b] = self.projects.as_slice() {
+                self.diff = Some(a.diff(b));
+                self.load = None;
+                return false;
+            }
+        }
+        true
+    }
+    /// The compared project `side` of the package shown in the sidebar
+    pub(crate) fn sidebar_project(&self) -> Option<(&Project, PkgKey)> {
+        let (side, key) = self.sidebar_pkg?;
+        let project = self.projects.get(side)?;
+        project.packages.contains_key(key).then_some((project, key))
+    }
+}
+
+pub(crate) fn compare_ui(ui: &mut egui::Ui, gui: &mut Gui, project: &Project, cfg: &Config) {
+    central_top_bar(ui, gui, project);
+    let Some(current) = gui.resolve_args.clone() else {
+        ui.label("Comparing is only available for projects loaded with cargo");
+        return;
+    };
+    let root = project.workspace_root.clone().into_std_path_buf();
+    let presets = cfg.feature_presets.get(&root).map_or(&[][..], Vec::as_slice);
+    egui::Grid::new("compare_sides_grid").show(ui, |ui| {
+        for (i, side) in gui.nav.compare.sides.iter_mut().enumerate() {
+            ui.label(egui::RichText::new(SIDE_NAMES[i]).strong());
+            let mut default = !side.no_default_features;
+            ui.checkbox(&mut default, "Default features");
+            side.no_default_features = !default;
+            ui.checkbox(&mut side.all_features, "All features");
+            let mut features = side.features.join(",");
+            if ui
+                .add(
+                    egui::TextEdit::singleline(&mut features)
+                        .hint_text("Features (comma separated)")
+                        .text_color(gui.style.colors.text_edit_text),
+                )
+                .changed()
+            {
+                side.features = features
+                    .split([',', ' '])
+                    .filter(|feat| !feat.is_empty())
+                    .map(str::to_owned)
+                    .collect();
+            }
+            ui.menu_button("Load", |ui| {
+                if ui.button("Current").clicked() {
+                    *side = FeaturePreset::from_args(String::new(), &current);
+                    ui.close_menu();
+                }
+                if ui.button("Defaults").clicked() {
+                    *side = FeaturePreset::default();
+                    ui.close_menu();
+                }
+                for preset in presets {
+                    if ui.button(&preset.name).clicked() {
+                        *side = preset.clone();
+                        ui.close_menu();
+                    }
+                }
+            });
+            ui.end_row();
+        }
+    });
+    let compare = &mut gui.nav.compare;
+    ui.horizontal(|ui| match &compare.load {
+        Some(load) => {
+            ui.spinner();
+            ui.label(format!(
+                "Resolving {}: {}",
+                SIDE_NAMES[compare.projects.len()],
+                load.status()
+            ));
+            if ui.button("Cancel").clicked() {
+                load.cancel();
+                compare.load = None;
+            }
+        }
+        None => {
+            if ui.button("Compare").clicked() {
+                compare.run_requested = true;
+            }
+        }
+    });
+    if let Some(error) = &compare.error {
+        ui.colored_label(egui::Color32::RED, error);
+    }
+    ui.separator();
+    let Some(diff) = &compare.diff else {
+        return;
+    };
+    let mut show = None;
+    ui.columns(3, |cols| {
+        cols[0].label(format!("Only in A ({})", diff.only_in_self.len()));
+        egui::ScrollArea::vertical().id_salt("only_a").show(&mut cols[0], |ui| {
+            for (name, version) in &diff.only_in_self {
+                pkg_entry_ui(ui, &gui.style, name, version, 0, &mut show);
+            }
+        });
+        cols[1].label(format!("Only in B ({})", diff.only_in_other.len()));
+        egui::ScrollArea::vertical().id_salt("only_b").show(&mut cols[1], |ui| {
+            for (name, version) in &diff.only_in_other {
+                pkg_entry_ui(ui, &gui.style, name, version, 1, &mut show);
+            }
+        });
+        cols[2].label(format!(
+            "Different features ({})",
+            diff.feature_changes.len()
+        ));
+        egui::ScrollArea::vertical().id_salt("feat_diff").show(&mut cols[2], |ui| {
+            for change in &diff.feature_changes {
+                pkg_entry_ui(ui, &gui.style, &change.name, &change.version, 0, &mut show);
+                ui.indent(("feat_change", &change.name, &change.version), |ui| {
+                    if !change.only_in_self.is_empty() {
+                        ui.label(format!("A only: {}", change.only_in_self.join(", ")));
+                    }
+                    if !change.only_in_other.is_empty() {
+                        ui.label(format!("B only: {}", change.only_in_other.join(", ")));
+                    }
+                });
+            }
+        });
+    });
+    if let Some((side, name, version)) = show {
+        show_pkg(gui, project, side, &name, &version);
+    }
+}
+
+fn pkg_entry_ui(
+    ui: &mut egui::Ui,
+    style: &crate::style::Style,
+    name: &str,
+    version: &Version,
+    side: usize,
+    show: &mut Option<(usize, String, Version)>,
+) {
+    ui.horizontal(|ui| {
+        let re = ui.selectable_label(
+            false,
+            egui::RichText::new(name).color(style.colors.highlighted_text),
+        );
+        ui.add(VersionBadge::new(version, style));
+        if re.clicked() {
+            *show = Some((side, name.to_owned(), version.clone()));
+        }
+    });
+}
+
+/// Show a package of the comparison in the sidebar.
+///
+/// If it's not in the current project, it's shown from the compared project of `side`.
+pub(crate) fn show_pkg(
+    gui: &mut Gui,
+    project: &Project,
+    side: usize,
+    name: &str,
+    version: &Version,
+) {
+    let compare = &mut gui.nav.compare;
+    match project.find_pkg(name, version) {
+        Some(key) => {
+            gui.nav.secondary_pkg = Some(key);
+            compare.sidebar_pkg = None;
+        }
+        None => {
+            gui.nav.secondary_pkg = None;
+            compare.sidebar_pkg = compare
+                .projects
+                .get(side)
+                .and_then(|proj| proj.find_pkg(name, version))
+                .map(|key| (side, key));
+        }
+    }
+    gui.show_sidebar = true;
+}
@@ -0,0 +1,250 @@
+use {
+    crate::{
+        cost::{direct_dep_costs, format_size, source_size, DepCost},
+        project::{PkgKey, Project},
+        ui::{central_top_bar, dim_off_target, widgets::DepkindBadge, Gui},
+    },
+    eframe::egui,
+    egui_extras::{Column, TableBuilder},
+    std::{path::PathBuf, sync::mpsc},
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum CostColumn {
+    Name,
+    Crates,
+    Size,
+    BuildScripts,
+    ProcMacros,
+}
+
+/// State of the dependency cost tab
+pub struct CostState {
+    /// The package the costs were computed for
+    root: Option<PkgKey>,
+    costs: Option<Vec<DepCost>>,
+    /// Source sizes by dependency, measured on a separate thread
+    sizes: Option<mpsc::Receiver<Vec<(PkgKey, u64)>>>,
+    sort: CostColumn,
+    descending: bool,
+}
+
+impl Default for CostState {
+    fn default() -> Self {
+        Self {
+            root: None,
+            costs: None,
+            sizes: None,
+            sort: CostColumn::Crates,
+            descending: true,
+        }
+    }
+}
+
+impl CostState {
+    /// Forget the computed costs, e.g. because the project was reloaded
+    pub fn invalidate(&mut self) {
+        self.costs = None;
+        self.sizes = None;
+    }
+    /// Compute the costs of the direct dependencies of `root`, and start measuring their sources
+    fn compute(&mut self, project: &Project, root: PkgKey, ctx: &egui::Context) {
+        let costs = direct_dep_costs(project, root);
+        let dirs: Vec<(PkgKey, Vec<PathBuf>)> = costs
+            .iter()
+            .map(|cost| {
+                let dirs = cost
+                    .exclusive
+                    .iter()
+                    .map(|key| project.packages[*key].manifest_dir.clone().into_std_path_buf())
+                    .collect();
+                (cost.dep, dirs)
+            })
+            .collect();
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let sizes = dirs
+                .into_iter()
+                .map(|(dep, dirs)| (dep, source_size(dirs.iter().map(PathBuf::as_path))))
+                .collect();
+            // Nobody is listening anymore if the costs were invalidated meanwhile
+            let _ = tx.send(sizes);
+            ctx.request_repaint();
+        });
+        self.root = Some(root);
+        self.costs = Some(costs);
+        self.sizes = Some(rx);
+        self.sort(project);
+    }
+    /// Fill in the source sizes, if they have been measured
+    fn poll_sizes(&mut self, project: &Project) {
+        let Some(sizes) = self.sizes.as_ref().and_then(|recv| recv.try_recv().ok()) else {
+            return;
+        };
+        self.sizes = None;
+        if let Some(costs) = &mut self.costs {
+            for (dep, size) in sizes {
+                if let Some(cost) = costs.iter_mut().find(|cost| cost.dep == dep) {
+                    cost.source_size = Some(size);
+                }
+            }
+        }
+        self.sort(project);
+    }
+    fn sort(&mut self, project: &Project) {
+        let Some(costs) = &mut self.costs else {
+            return;
+        };
+        costs.sort_by(|a, b| {
+            let name = |cost: &DepCost| &project.packages[cost.dep].cm_pkg.name;
+            let ord = match self.sort {
+                CostColumn::Name => name(a).cmp(name(b)),
+                CostColumn::Crates => a.exclusive.len().cmp(&b.exclusive.len()),
+                CostColumn::Size => a.source_size.cmp(&b.source_size),
+                CostColumn::BuildScripts => a.build_scripts.cmp(&b.build_scripts),
+                CostColumn::ProcMacros => a.proc_macros.cmp(&b.proc_macros),
+            };
+            let ord = if self.descending { ord.reverse() } else { ord };
+            ord.then_with(|| name(a).cmp(name(b)))
+        });
+    }
+}
+
+/// What each direct dependency of a workspace member brings into the graph
+pub(crate) fn cost_ui(ui: &mut egui::Ui, gui: &mut Gui, project: &Project) {
+    central_top_bar(ui, gui, project);
+    let root = gui
+        .nav
+        .primary_pkg
+        .filter(|key| project.is_workspace_member(*key))
+        .or(project.root)
+        .or_else(|| project.initial_pkg());
+    let Some(root) = root else {
+        ui.label("There is no workspace member to show the dependencies of");
+        return;
+    };
+    let state = &mut gui.nav.cost;
+    if state.costs.is_none() || state.root != Some(root) {
+        state.compute(project, root, ui.ctx());
+    }
+    state.poll_sizes(project);
+    ui.horizontal(|ui| {
+        ui.heading(format!(
+            "Direct dependencies of {}",
+            project.packages[root].cm_pkg.name
+        ));
+        if ui.button("Recompute").on_hover_text("Measure the source sizes again").clicked() {
+            state.invalidate();
+        }
+    });
+    ui.label("Removing a dependency would drop the packages only it pulls in");
+    ui.separator();
+    let Some(costs) = &state.costs else {
+        return;
+    };
+    let mut clicked_header = None;
+    let mut selected = None;
+    TableBuilder::new(ui)
+        .striped(true)
+        .column(Column::auto().at_least(160.0))
+        .column(Column::auto())
+        .columns(Column::auto().at_least(80.0), 4)
+        .header(22.0, |mut row| {
+            for (column, title) in [
+                (Some(CostColumn::Name), "Dependency"),
+                (None, "Kind"),
+                (Some(CostColumn::Crates), "Removes"),
+                (Some(CostColumn::Size), "Source size"),
+                (Some(CostColumn::BuildScripts), "Build scripts"),
+                (Some(CostColumn::ProcMacros), "Proc-macros"),
+            ] {
+                row.col(|ui| {
+                    let Some(column) = column else {
+                        ui.strong(title);
+                        return;
+                    };
+                    let arrow = match (state.sort == column, state.descending) {
+                        (true, true) => " ⏷",
+                        (true, false) => " ⏶",
+                        (false, _) => "",
+                    };
+                    if ui.button(format!("{title}{arrow}")).clicked() {
+                        clicked_header = Some(column);
+                    }
+                });
+            }
+        })
+        .body(|mut body| {
+            for cost in costs {
+                let pkg = &project.packages[cost.dep];
+                let link = project.packages[root]
+                    .dependencies
+                    .iter()
+                    .find(|link| link.pkg_key == cost.dep);
+                if gui.hide_off_target && link.is_some_and(|link| !project.link_on_target(link)) {
+                    continue;
+                }
+                body.row(22.0, |mut row| {
+                    row.col(|ui| {
+                        dim_off_target(ui, pkg.on_target);
+                        let re = ui.selectable_label(
+                            gui.nav.secondary_pkg == Some(cost.dep),
+                            egui::RichText::new(&pkg.cm_pkg.name)
+                                .color(gui.style.colors.highlighted_text),
+                        );
+                        if re.clicked() {
+                            selected = Some(cost.dep);
+                        }
+                    });
+                    row.col(|ui| {
+                        for kind in link.iter().flat_map(|link| &link.kinds) {
+                            ui.add(DepkindBadge::new(kind.kind, &gui.style));
+                        }
+                    });
+                    row.col(|ui| {
+                        let re = ui.label(format!("{} crates", cost.exclusive.len()));
+                        if !cost.exclusive.is_empty() {
+                            re.on_hover_ui(|ui| {
+                                let mut names: Vec<String> = cost
+                                    .exclusive
+                                    .iter()
+                                    .map(|key| {
+                                        let pkg = &project.packages[*key].cm_pkg;
+                                        format!("{} {}", pkg.name, pkg.version)
+                                    })
+                                    .collect();
+                                names.sort();
+                                ui.label(names.join("\n"));
+                            });
+                        }
+                    });
+                    row.col(|ui| {
+                        match cost.source_size {
+                            Some(size) => ui.label(format_size(size)),
+                            None => ui.spinner(),
+                        };
+                    });
+                    row.col(|ui| {
+                        ui.label(cost.build_scripts.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(cost.proc_macros.to_string());
+                    });
+                });
+            }
+        });
+    if let Some(column) = clicked_header {
+        if state.sort == column {
+            state.descending ^= true;
+        } else {
+            state.sort = column;
+            state.descending = column != CostColumn::Name;
+        }
+        state.sort(project);
+    }
+    if let Some(key) = selected {
+        gui.nav.secondary_pkg = Some(key);
+        gui.show_sidebar = true;
+    }
+}
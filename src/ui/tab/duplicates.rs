@@ -0,0 +1,187 @@
+use {
+    super::Tab,
+    crate::{
+        duplicates::{find_duplicates, Duplicate, DuplicateVersion, Requirement, Suggestion},
+        project::{PkgKey, Project},
+        style::Style,
+        ui::{
+            central_top_bar, dim_off_target,
+            widgets::{badge, VersionBadge},
+            Gui,
+        },
+    },
+    eframe::egui,
+};
+
+/// State of the duplicates tab
+#[derive(Default)]
+pub struct DuplicatesState {
+    duplicates: Option<Vec<Duplicate>>,
+}
+
+impl DuplicatesState {
+    /// Forget the found duplicates, e.g. because the project was reloaded
+    pub fn invalidate(&mut self) {
+        self.duplicates = None;
+    }
+}
+
+enum Action {
+    Select(PkgKey),
+    Why(PkgKey),
+}
+
+/// Crates with more than one version, who pulls in each version, and how to unify them
+pub(crate) fn duplicates_ui(ui: &mut egui::Ui, gui: &mut Gui, project: &Project) {
+    central_top_bar(ui, gui, project);
+    let duplicates = gui.nav.duplicates.duplicates.get_or_insert_with(|| find_duplicates(project));
+    let duplicates: Vec<(&Duplicate, Vec<&DuplicateVersion>)> = duplicates
+        .iter()
+        .map(|dup| {
+            let versions: Vec<&DuplicateVersion> = dup
+                .versions
+                .iter()
+                .filter(|ver| !gui.hide_off_target || project.packages[ver.key].on_target)
+                .collect();
+            (dup, versions)
+        })
+        .filter(|(_, versions)| versions.len() > 1)
+        .collect();
+    let extra: usize = duplicates.iter().map(|(_, versions)| versions.len() - 1).sum();
+    ui.label(format!(
+        "{} crates with more than one version ({extra} extra packages)",
+        duplicates.len()
+    ));
+    if project.lockfile_only {
+        ui.label(
+            "Requirements are unknown with --lockfile-only, so no way to unify the versions \
+             can be suggested",
+        );
+    }
+    ui.separator();
+    let mut action = None;
+    egui::ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
+        for (dup, versions) in &duplicates {
+            let version_list: Vec<String> = versions
+                .iter()
+                .map(|ver| project.packages[ver.key].cm_pkg.version.to_string())
+                .collect();
+            egui::CollapsingHeader::new(format!("{} ({})", dup.name, version_list.join(", ")))
+                .id_salt(("duplicate", &dup.name))
+                .show(ui, |ui| {
+                    duplicate_ui(ui, dup, versions, project, &gui.style, &mut action)
+                });
+        }
+    });
+    match action {
+        Some(Action::Select(key)) => {
+            gui.nav.secondary_pkg = Some(key);
+            gui.show_sidebar = true;
+        }
+        Some(Action::Why(key)) => {
+            gui.nav.inverse_pkg = Some(key);
+            gui.nav.tab = Tab::Inverse;
+        }
+        None => {}
+    }
+}
+
+fn duplicate_ui(
+    ui: &mut egui::Ui,
+    dup: &Duplicate,
+    versions: &[&DuplicateVersion],
+    project: &Project,
+    style: &Style,
+    action: &mut Option<Action>,
+) {
+    for ver in versions {
+        let pkg = &project.packages[ver.key];
+        ui.horizontal(|ui| {
+            dim_off_target(ui, pkg.on_target);
+            ui.add(VersionBadge::new(&pkg.cm_pkg.version, style));
+            ui.label(format!("required by {}", ver.dependents.len()));
+            if ui.button("Why here?").clicked() {
+                *action = Some(Action::Why(ver.key));
+            }
+        });
+        ui.indent(("duplicate_version", ver.key), |ui| {
+            for req in &ver.dependents {
+                requirement_ui(ui, req, project, style, action);
+            }
+        });
+    }
+    match &dup.suggestion {
+        Some(suggestion) => suggestion_ui(ui, dup, suggestion, project),
+        None => {
+            ui.label("Requirements are unknown, so no way to unify the versions can be suggested");
+        }
+    }
+}
+
+fn requirement_ui(
+    ui: &mut egui::Ui,
+    req: &Requirement,
+    project: &Project,
+    style: &Style,
+    action: &mut Option<Action>,
+) {
+    let dependent = &project.packages[req.dependent];
+    ui.horizontal(|ui| {
+        dim_off_target(ui, dependent.on_target);
+        if ui
+            .selectable_label(
+                false,
+                egui::RichText::new(&dependent.cm_pkg.name).color(style.colors.highlighted_text),
+            )
+            .clicked()
+        {
+            *action = Some(Action::Select(dependent.key));
+        }
+        ui.add(VersionBadge::new(&dependent.cm_pkg.version, style));
+        if project.is_workspace_member(dependent.key) {
+            badge(
+                ui,
+                "member",
+                egui::Color32::DARK_BLUE,
+                egui::Color32::LIGHT_BLUE,
+            );
+        }
+        match &req.req {
+            Some(req) => ui.code(req.to_string()),
+            None => ui.weak("unknown requirement"),
+        };
+    });
+}
+
+fn suggestion_ui(ui: &mut egui::Ui, dup: &Duplicate, suggestion: &Suggestion, project: &Project) {
+    let target = &project.packages[suggestion.target].cm_pkg.version;
+    ui.label(egui::RichText::new(format!("To unify on {target}:")).strong());
+    for change in &suggestion.changes {
+        let dependent = &project.packages[change.dependent].cm_pkg;
+        let current = &project.packages[change.current].cm_pkg.version;
+        let text = match &change.new_req {
+            Some(new_req) => format!(
+                "{} {}'s req on {} from {} to {new_req}",
+                if target > current { "bump" } else { "lower" },
+                dependent.name,
+                dup.name,
+                change.old_req
+            ),
+            None => format!(
+                "{}'s req on {} ({}) already allows it: `cargo update -p {}@{current}`",
+                dependent.name, dup.name, change.old_req, dup.name
+            ),
+        };
+        ui.label(format!("• {text}"));
+    }
+    let upstream = suggestion
+        .changes
+        .iter()
+        .filter(|change| change.new_req.is_some() && !project.is_workspace_member(change.dependent))
+        .count();
+    if upstream > 0 {
+        ui.weak(format!(
+            "{upstream} of these changes are in dependencies, and need a new release (or a patch)"
+        ));
+    }
+}
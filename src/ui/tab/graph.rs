@@ -0,0 +1,526 @@
+use {
+    super::Tab,
+    crate::{
+        project::{BuildContext, DepLink, PkgKey, Project},
+        ui::{central_top_bar, Gui},
+    },
+    cargo_metadata::DependencyKind,
+    eframe::egui::{self, epaint::CubicBezierShape, pos2, vec2, Color32, Pos2, Rect, Stroke, Vec2},
+    std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque},
+};
+
+const NODE_HEIGHT: f32 = 22.0;
+const NODE_GAP: f32 = 16.0;
+const LAYER_GAP: f32 = 90.0;
+const FONT_SIZE: f32 = 14.0;
+/// Number of barycenter sweeps for ordering the nodes within their layers
+const ORDERING_SWEEPS: usize = 8;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorBy {
+    /// Whether the package is needed for normal, build or dev dependencies
+    Kind,
+    /// Where the package comes from: crates.io, another registry, git or a local path
+    Source,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum GraphMode {
+    /// Everything reachable from the workspace members, optionally up to a depth
+    Whole,
+    /// The dependencies and dependents of the selected package, up to a distance
+    Neighbourhood,
+}
+
+/// State of the graph tab
+pub struct GraphState {
+    pub color_by: ColorBy,
+    pub mode: GraphMode,
+    /// Maximum depth from the workspace members in [`GraphMode::Whole`]
+    pub depth_limit: Option<usize>,
+    /// Maximum distance from the selected package in [`GraphMode::Neighbourhood`]
+    pub radius: usize,
+    pub show_normal: bool,
+    pub show_build: bool,
+    pub show_dev: bool,
+    /// Pan offset of the view, in screen space
+    offset: Vec2,
+    zoom: f32,
+    layout: Option<GraphLayout>,
+}
+
+impl Default for GraphState {
+    fn default() -> Self {
+        Self {
+            color_by: ColorBy::Kind,
+            mode: GraphMode::Whole,
+            depth_limit: None,
+            radius: 2,
+            show_normal: true,
+            show_build: true,
+            show_dev: true,
+            offset: Vec2::ZERO,
+            zoom: 1.0,
+            layout: None,
+        }
+    }
+}
+
+impl GraphState {
+    /// Forget the layout, e.g. because the project was reloaded
+    pub fn invalidate(&mut self) {
+        self.layout = None;
+    }
+    fn follows(&self, link: &DepLink) -> bool {
+        // Links of unknown kind (with `--lockfile-only`) count as normal
+        if link.kinds.is_empty() {
+            return self.show_normal;
+        }
+        link.kinds.iter().any(|kind| match kind.kind {
+            DependencyKind::Build => self.show_build,
+            DependencyKind::Development => self.show_dev,
+            _ => self.show_normal,
+        })
+    }
+    fn params(&self, roots: Vec<PkgKey>, hide_off_target: bool, project: &Project) -> LayoutParams {
+        LayoutParams {
+            roots,
+            mode: self.mode,
+            depth_limit: self.depth_limit,
+            radius: self.radius,
+            kinds: [self.show_normal, self.show_build, self.show_dev],
+            hide_off_target,
+            target: project.target.as_ref().map(|target| target.triple.clone()),
+        }
+    }
+}
+
+/// What a layout was computed for
+#[derive(PartialEq)]
+struct LayoutParams {
+    /// The packages on the top layer of the whole graph, or the center of the neighbourhood
+    roots: Vec<PkgKey>,
+    mode: GraphMode,
+    depth_limit: Option<usize>,
+    radius: usize,
+    kinds: [bool; 3],
+    hide_off_target: bool,
+    target: Option<String>,
+}
+
+struct GraphNode {
+    key: PkgKey,
+    layer: i32,
+    /// Center of the node, in graph space
+    pos: Pos2,
+    width: f32,
+    /// Number of dependencies that aren't shown
+    hidden_deps: usize,
+}
+
+/// A layered drawing of (a part of) the dependency graph
+struct GraphLayout {
+    params: LayoutParams,
+    nodes: Vec<GraphNode>,
+    /// Dependent and dependency, as indices into `nodes`
+    edges: Vec<(usize, usize)>,
+    /// What each package is built for, see [`Project::build_contexts`]
+    contexts: HashMap<PkgKey, BuildContext>,
+}
+
+impl GraphLayout {
+    fn compute(
+        project: &Project,
+        state: &GraphState,
+        params: LayoutParams,
+        ctx: &egui::Context,
+    ) -> Self {
+        let link_ok = |link: &DepLink| {
+            state.follows(link) && (!params.hide_off_target || project.link_on_target(link))
+        };
+        // Assign layers by breadth first search from the roots
+        let mut layers: HashMap<PkgKey, i32> = params.roots.iter().map(|&key| (key, 0)).collect();
+        let max_dist = match params.mode {
+            GraphMode::Whole => params.depth_limit.unwrap_or(usize::MAX),
+            GraphMode::Neighbourhood => params.radius,
+        };
+        let mut queue: VecDeque<(PkgKey, usize)> =
+            params.roots.iter().map(|&key| (key, 0)).collect();
+        while let Some((key, dist)) = queue.pop_front() {
+            if dist >= max_dist {
+                continue;
+            }
+            for link in project.packages[key].dependencies.iter().filter(|l| link_ok(l)) {
+                if let Entry::Vacant(slot) = layers.entry(link.pkg_key) {
+                    slot.insert(dist as i32 + 1);
+                    queue.push_back((link.pkg_key, dist + 1));
+                }
+            }
+        }
+        if params.mode == GraphMode::Neighbourhood {
+            // Dependents go above the center
+            let mut queue: VecDeque<(PkgKey, usize)> =
+                params.roots.iter().map(|&key| (key, 0)).collect();
+            while let Some((key, dist)) = queue.pop_front() {
+                if dist >= max_dist {
+                    continue;
+                }
+                for link in project.packages[key].dependents.iter().filter(|l| link_ok(l)) {
+                    if let Entry::Vacant(slot) = layers.entry(link.pkg_key) {
+                        slot.insert(-(dist as i32) - 1);
+                        queue.push_back((link.pkg_key, dist + 1));
+                    }
+                }
+            }
+        }
+        let mut nodes: Vec<GraphNode> = layers
+            .iter()
+            .map(|(&key, &layer)| GraphNode {
+                key,
+                layer,
+                pos: Pos2::ZERO,
+                width: ctx.fonts(|fonts| {
+                    fonts
+                        .layout_no_wrap(
+                            project.packages[key].cm_pkg.name.clone(),
+                            egui::FontId::proportional(FONT_SIZE),
+                            Color32::WHITE,
+                        )
+                        .size()
+                        .x
+                }) + 12.0,
+                hidden_deps: 0,
+            })
+            .collect();
+        nodes.sort_by(|a, b| {
+            let (pa, pb) = (
+                &project.packages[a.key].cm_pkg,
+                &project.packages[b.key].cm_pkg,
+            );
+            (a.layer, &pa.name, &pa.version).cmp(&(b.layer, &pb.name, &pb.version))
+        });
+        let index: HashMap<PkgKey, usize> =
+            nodes.iter().enumerate().map(|(i, node)| (node.key, i)).collect();
+        let mut edges = Vec::new();
+        for (i, node) in nodes.iter_mut().enumerate() {
+            for link in project.packages[node.key].dependencies.iter().filter(|l| link_ok(l)) {
+                match index.get(&link.pkg_key) {
+                    Some(&dep) => edges.push((i, dep)),
+                    None => node.hidden_deps += 1,
+                }
+            }
+        }
+        let mut layout = Self {
+            params,
+            nodes,
+            edges,
+            contexts: project.build_contexts(),
+        };
+        layout.order_layers();
+        layout
+    }
+    /// Order the nodes of each layer to reduce edge crossings, and position them
+    fn order_layers(&mut self) {
+        let (min, max) = self.nodes.iter().fold((0, 0), |(min, max), node| {
+            (node.layer.min(min), node.layer.max(max))
+        });
+        let mut layers: Vec<Vec<usize>> = vec![Vec::new(); (max - min + 1) as usize];
+        for (i, node) in self.nodes.iter().enumerate() {
+            layers[(node.layer - min) as usize].push(i);
+        }
+        let mut neighbours: Vec<Vec<usize>> = vec![Vec::new(); self.nodes.len()];
+        for &(from, to) in &self.edges {
+            neighbours[from].push(to);
+            neighbours[to].push(from);
+        }
+        // Position of each node within its layer
+        let mut order: Vec<f32> = vec![0.0; self.nodes.len()];
+        for layer in &layers {
+            for (pos, &i) in layer.iter().enumerate() {
+                order[i] = pos as f32;
+            }
+        }
+        for sweep in 0..ORDERING_SWEEPS {
+            let down = sweep % 2 == 0;
+            let layer_indices: Vec<usize> = if down {
+                (1..layers.len()).collect()
+            } else {
+                (0..layers.len().saturating_sub(1)).rev().collect()
+            };
+            for l in layer_indices {
+                let adjacent_layer = if down { l - 1 } else { l + 1 } as i32 + min;
+                let bary = |i: usize| {
+                    let adjacent: Vec<f32> = neighbours[i]
+                        .iter()
+                        .filter(|&&n| self.nodes[n].layer == adjacent_layer)
+                        .map(|&n| order[n])
+                        .collect();
+                    if adjacent.is_empty() {
+                        order[i]
+                    } else {
+                        adjacent.iter().sum::<f32>() / adjacent.len() as f32
+                    }
+                };
+                let mut keyed: Vec<(f32, usize)> =
+                    layers[l].iter().map(|&i| (bary(i), i)).collect();
+                keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+                layers[l] = keyed.into_iter().map(|(_, i)| i).collect();
+                for (pos, &i) in layers[l].iter().enumerate() {
+                    order[i] = pos as f32;
+                }
+            }
+        }
+        for (l, layer) in layers.iter().enumerate() {
+            let total: f32 = layer.iter().map(|&i| self.nodes[i].width + NODE_GAP).sum();
+            let mut x = -total / 2.0;
+            for &i in layer {
+                let node = &mut self.nodes[i];
+                node.pos = pos2(x + node.width / 2.0, (l as i32 + min) as f32 * LAYER_GAP);
+                x += node.width + NODE_GAP;
+            }
+        }
+    }
+}
+
+const MEMBER_COLOR: Color32 = Color32::from_rgb(110, 50, 120);
+const NORMAL_COLOR: Color32 = Color32::from_rgb(40, 70, 120);
+const BUILD_COLOR: Color32 = Color32::from_rgb(120, 70, 40);
+const DEV_COLOR: Color32 = Color32::from_rgb(50, 100, 40);
+const CRATES_IO_COLOR: Color32 = NORMAL_COLOR;
+const GIT_COLOR: Color32 = Color32::from_rgb(30, 100, 60);
+const REGISTRY_COLOR: Color32 = Color32::from_rgb(40, 100, 110);
+const PATH_COLOR: Color32 = Color32::from_rgb(120, 100, 30);
+
+const KIND_LEGEND: &[(&str, Color32)] = &[
+    ("member", MEMBER_COLOR),
+    ("normal", NORMAL_COLOR),
+    ("build", BUILD_COLOR),
+    ("dev", DEV_COLOR),
+];
+const SOURCE_LEGEND: &[(&str, Color32)] = &[
+    ("member", MEMBER_COLOR),
+    ("crates.io", CRATES_IO_COLOR),
+    ("git", GIT_COLOR),
+    ("other registry", REGISTRY_COLOR),
+    ("path", PATH_COLOR),
+];
+
+fn node_color(project: &Project, key: PkgKey, color_by: ColorBy, context: BuildContext) -> Color32 {
+    if project.is_workspace_member(key) {
+        return MEMBER_COLOR;
+    }
+    match color_by {
+        ColorBy::Kind => match context {
+            BuildContext::Normal => NORMAL_COLOR,
+            BuildContext::Build => BUILD_COLOR,
+            BuildContext::Dev => DEV_COLOR,
+        },
+        ColorBy::Source => match &project.packages[key].cm_pkg.source {
+            Some(source) if source.is_crates_io() => CRATES_IO_COLOR,
+            Some(source) if source.repr.starts_with("git+") => GIT_COLOR,
+            Some(_) => REGISTRY_COLOR,
+            None => PATH_COLOR,
+        },
+    }
+}
+
+pub(crate) fn graph_ui(ui: &mut egui::Ui, gui: &mut Gui, project: &Project) {
+    central_top_bar(ui, gui, project);
+    let state = &mut gui.nav.graph;
+    ui.horizontal_wrapped(|ui| {
+        ui.selectable_value(&mut state.mode, GraphMode::Whole, "Whole graph");
+        ui.selectable_value(&mut state.mode, GraphMode::Neighbourhood, "Neighbourhood")
+            .on_hover_text("Only the packages around the selected package");
+        match state.mode {
+            GraphMode::Whole => {
+                let mut limited = state.depth_limit.is_some();
+                ui.checkbox(&mut limited, "Depth limit");
+                let mut limit = state.depth_limit.unwrap_or(3);
+                ui.add_enabled(limited, egui::DragValue::new(&mut limit).range(1..=100));
+                state.depth_limit = limited.then_some(limit);
+            }
+            GraphMode::Neighbourhood => {
+                ui.label("Distance");
+                ui.add(egui::DragValue::new(&mut state.radius).range(1..=20));
+            }
+        }
+        ui.separator();
+        ui.label("Edges");
+        ui.checkbox(&mut state.show_normal, "normal");
+        ui.checkbox(&mut state.show_build, "build");
+        ui.checkbox(&mut state.show_dev, "dev");
+        ui.separator();
+        ui.label("Color by");
+        ui.selectable_value(&mut state.color_by, ColorBy::Kind, "kind");
+        ui.selectable_value(&mut state.color_by, ColorBy::Source, "source");
+        let legend = match state.color_by {
+            ColorBy::Kind => KIND_LEGEND,
+            ColorBy::Source => SOURCE_LEGEND,
+        };
+        for (label, color) in legend {
+            egui::Frame::new().fill(*color).inner_margin(2.0).show(ui, |ui| {
+                ui.label(egui::RichText::new(*label).color(Color32::WHITE));
+            });
+        }
+        ui.separator();
+        if ui.button("Reset view").clicked() {
+            state.offset = Vec2::ZERO;
+            state.zoom = 1.0;
+        }
+    });
+    let roots: Vec<PkgKey> = match state.mode {
+        GraphMode::Whole => project.workspace_members.clone(),
+        GraphMode::Neighbourhood => gui
+            .nav
+            .secondary_pkg
+            .or(gui.nav.primary_pkg)
+            .filter(|key| project.packages.contains_key(*key))
+            .into_iter()
+            .collect(),
+    };
+    if roots.is_empty() {
+        ui.label("Select a package to show the neighbourhood of");
+        return;
+    }
+    let params = state.params(roots, gui.hide_off_target, project);
+    if state.layout.as_ref().is_none_or(|layout| layout.params != params) {
+        state.layout = Some(GraphLayout::compute(project, state, params, ui.ctx()));
+    }
+    let Some(layout) = &state.layout else {
+        return;
+    };
+    let (rect, re) = ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, gui.style.colors.extreme_bg);
+    // Pan and zoom
+    if re.dragged() {
+        state.offset += re.drag_delta();
+    }
+    if let (true, Some(pointer)) = (re.hovered(), re.hover_pos()) {
+        let (scroll, pinch) = ui.input(|inp| (inp.smooth_scroll_delta.y, inp.zoom_delta()));
+        let factor = pinch * (scroll * 0.002).exp();
+        if factor != 1.0 {
+            let new_zoom = (state.zoom * factor).clamp(0.05, 4.0);
+            // Keep the point under the pointer in place
+            let anchor = pointer - rect.center() - state.offset;
+            state.offset -= anchor * (new_zoom / state.zoom - 1.0);
+            state.zoom = new_zoom;
+        }
+    }
+    let (zoom, offset) = (state.zoom, state.offset);
+    let to_screen = |pos: Pos2| rect.center() + offset + pos.to_vec2() * zoom;
+    let node_rect = |node: &GraphNode| {
+        Rect::from_center_size(to_screen(node.pos), vec2(node.width, NODE_HEIGHT) * zoom)
+    };
+    let hovered = re
+        .hover_pos()
+        .and_then(|pointer| layout.nodes.iter().position(|node| node_rect(node).contains(pointer)));
+    // Everything on a path from the roots to the hovered node
+    let on_path: HashSet<usize> = match hovered {
+        Some(hovered) => {
+            let mut preds: Vec<Vec<usize>> = vec![Vec::new(); layout.nodes.len()];
+            for &(from, to) in &layout.edges {
+                preds[to].push(from);
+            }
+            let mut seen = HashSet::from([hovered]);
+            let mut stack = vec![hovered];
+            while let Some(i) = stack.pop() {
+                for &pred in &preds[i] {
+                    if seen.insert(pred) {
+                        stack.push(pred);
+                    }
+                }
+            }
+            seen
+        }
+        None => HashSet::new(),
+    };
+    let highlighting = hovered.is_some();
+    let clip = rect.expand(20.0);
+    for &(from, to) in &layout.edges {
+        let (a, b) = (node_rect(&layout.nodes[from]), node_rect(&layout.nodes[to]));
+        if !clip.intersects(a.union(b)) {
+            continue;
+        }
+        let highlighted = on_path.contains(&from) && on_path.contains(&to);
+        let stroke = if highlighted {
+            Stroke::new(2.0, Color32::YELLOW)
+        } else if highlighting {
+            Stroke::new(1.0, Color32::from_gray(50))
+        } else {
+            Stroke::new(1.0, Color32::from_gray(110))
+        };
+        let (start, end) = (a.center_bottom(), b.center_top());
+        let bend = LAYER_GAP * zoom * 0.5;
+        painter.add(CubicBezierShape::from_points_stroke(
+            [start, start + vec2(0.0, bend), end - vec2(0.0, bend), end],
+            false,
+            Color32::TRANSPARENT,
+            stroke,
+        ));
+    }
+    let show_text = zoom > 0.3;
+    for (i, node) in layout.nodes.iter().enumerate() {
+        let node_rect = node_rect(node);
+        if !clip.intersects(node_rect) {
+            continue;
+        }
+        let pkg = &project.packages[node.key];
+        let context = layout.contexts.get(&node.key).copied().unwrap_or(BuildContext::Dev);
+        let mut fill = node_color(project, node.key, state.color_by, context);
+        if highlighting && !on_path.contains(&i) {
+            fill = fill.gamma_multiply(0.3);
+        }
+        let stroke = if gui.nav.secondary_pkg == Some(node.key) {
+            Stroke::new(2.0, Color32::WHITE)
+        } else if hovered == Some(i) {
+            Stroke::new(2.0, Color32::YELLOW)
+        } else {
+            Stroke::NONE
+        };
+        painter.rect(
+            node_rect,
+            4.0 * zoom,
+            fill,
+            stroke,
+            egui::StrokeKind::Outside,
+        );
+        if show_text {
+            painter.text(
+                node_rect.center(),
+                egui::Align2::CENTER_CENTER,
+                &pkg.cm_pkg.name,
+                egui::FontId::proportional(FONT_SIZE * zoom),
+                Color32::WHITE,
+            );
+            if node.hidden_deps > 0 {
+                painter.text(
+                    node_rect.center_bottom() + vec2(0.0, 2.0 * zoom),
+                    egui::Align2::CENTER_TOP,
+                    format!("+{}", node.hidden_deps),
+                    egui::FontId::proportional(FONT_SIZE * 0.8 * zoom),
+                    Color32::GRAY,
+                );
+            }
+        }
+    }
+    if let Some(i) = hovered {
+        let pkg = &project.packages[layout.nodes[i].key];
+        let mut tip = format!("{} {}", pkg.cm_pkg.name, pkg.cm_pkg.version);
+        if layout.nodes[i].hidden_deps > 0 {
+            tip.push_str(&format!(
+                "\n{} dependencies not shown",
+                layout.nodes[i].hidden_deps
+            ));
+        }
+        re.clone().on_hover_text_at_pointer(tip);
+        let key = layout.nodes[i].key;
+        if re.double_clicked() {
+            gui.nav.primary_pkg = Some(key);
+            gui.nav.tab = Tab::ViewSingle;
+        } else if re.clicked() {
+            gui.nav.secondary_pkg = Some(key);
+            gui.show_sidebar = true;
+        }
+    }
+}
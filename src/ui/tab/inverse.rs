@@ -0,0 +1,164 @@
+use {
+    super::Tab,
+    crate::{
+        project::{BuildContext, DepLink, PkgKey, Project},
+        style::Style,
+        ui::{
+            central_top_bar, dim_off_target,
+            widgets::{badge, DepkindBadge, VersionBadge},
+            Gui,
+        },
+    },
+    eframe::egui,
+    std::collections::HashMap,
+};
+
+enum Action {
+    Select(PkgKey),
+    Focus(PkgKey),
+}
+
+/// State shared by the nodes of the inverse tree while drawing
+struct InverseTree<'a> {
+    project: &'a Project,
+    style: &'a Style,
+    hide_off_target: bool,
+    contexts: HashMap<PkgKey, BuildContext>,
+    /// Packages leading down to the current one, to avoid expanding dev dependency cycles
+    path: Vec<PkgKey>,
+    action: Option<Action>,
+}
+
+impl InverseTree<'_> {
+    /// The context of the path going up through `link` from the package it depends on
+    fn path_context(&self, link: &DepLink) -> BuildContext {
+        let dependent = self.contexts.get(&link.pkg_key).copied().unwrap_or(BuildContext::Dev);
+        dependent.max(BuildContext::of_link(link))
+    }
+}
+
+/// Every path from the workspace members down to a package, like `cargo tree --invert`
+pub(crate) fn inverse_ui(ui: &mut egui::Ui, gui: &mut Gui, project: &Project) {
+    central_top_bar(ui, gui, project);
+    let Some(pkg) = gui.nav.inverse_pkg.and_then(|key| project.packages.get(key)) else {
+        ui.label("Use \"Why here?\" on a package to see the paths leading to it");
+        return;
+    };
+    let mut tree = InverseTree {
+        project,
+        style: &gui.style,
+        hide_off_target: gui.hide_off_target,
+        contexts: project.build_contexts(),
+        path: vec![pkg.key],
+        action: None,
+    };
+    ui.horizontal(|ui| {
+        ui.heading(format!("Why is {} here?", pkg.cm_pkg.name));
+        ui.add(VersionBadge::new(&pkg.cm_pkg.version, tree.style));
+    });
+    ui.label(match tree.contexts.get(&pkg.key) {
+        _ if project.is_workspace_member(pkg.key) => "Workspace member",
+        Some(BuildContext::Normal) => "Used by normal dependencies",
+        Some(BuildContext::Build) => "Only used by build dependencies",
+        Some(BuildContext::Dev) | None => "Only used by dev dependencies",
+    });
+    ui.separator();
+    egui::ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
+        dependents_ui(ui, &mut tree, pkg.key);
+    });
+    match tree.action {
+        Some(Action::Select(key)) => {
+            gui.nav.secondary_pkg = Some(key);
+            gui.show_sidebar = true;
+        }
+        Some(Action::Focus(key)) => {
+            gui.nav.primary_pkg = Some(key);
+            gui.nav.tab = Tab::ViewSingle;
+        }
+        None => {}
+    }
+}
+
+/// The dependents of `key`, with dev-only and build-only paths grouped separately
+fn dependents_ui(ui: &mut egui::Ui, tree: &mut InverseTree, key: PkgKey) {
+    let project = tree.project;
+    let mut groups: [Vec<&DepLink>; 3] = Default::default();
+    for link in &project.packages[key].dependents {
+        if tree.hide_off_target && !project.link_on_target(link) {
+            continue;
+        }
+        groups[tree.path_context(link) as usize].push(link);
+    }
+    let [normal, build, dev] = groups;
+    for link in normal {
+        dependent_ui(ui, tree, link);
+    }
+    for (links, label) in [(build, "Build-only paths"), (dev, "Dev-only paths")] {
+        if links.is_empty() {
+            continue;
+        }
+        egui::CollapsingHeader::new(format!("{label} ({})", links.len()))
+            .id_salt((label, &tree.path))
+            .show(ui, |ui| {
+                for link in links {
+                    dependent_ui(ui, tree, link);
+                }
+            });
+    }
+}
+
+/// A dependent, expandable into its own dependents
+fn dependent_ui(ui: &mut egui::Ui, tree: &mut InverseTree, link: &DepLink) {
+    let project = tree.project;
+    let key = link.pkg_key;
+    let cycle = tree.path.contains(&key);
+    if cycle || project.packages[key].dependents.is_empty() {
+        ui.horizontal(|ui| {
+            // Line up with the expandable siblings
+            ui.add_space(ui.spacing().indent);
+            dependent_row_ui(ui, tree, link, cycle);
+        });
+        return;
+    }
+    let id = ui.make_persistent_id(("inverse_node", &tree.path, key));
+    egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, false)
+        .show_header(ui, |ui| dependent_row_ui(ui, tree, link, false))
+        .body(|ui| {
+            tree.path.push(key);
+            dependents_ui(ui, tree, key);
+            tree.path.pop();
+        });
+}
+
+fn dependent_row_ui(ui: &mut egui::Ui, tree: &mut InverseTree, link: &DepLink, cycle: bool) {
+    let pkg = &tree.project.packages[link.pkg_key];
+    dim_off_target(ui, tree.project.link_on_target(link));
+    let re = ui.selectable_label(
+        false,
+        egui::RichText::new(&pkg.cm_pkg.name).color(tree.style.colors.highlighted_text),
+    );
+    if re.clicked() {
+        tree.action = Some(Action::Select(pkg.key));
+    }
+    if re.double_clicked() {
+        tree.action = Some(Action::Focus(pkg.key));
+    }
+    ui.add(VersionBadge::new(&pkg.cm_pkg.version, tree.style));
+    for kind in &link.kinds {
+        ui.add(DepkindBadge::new(kind.kind, tree.style));
+        if let Some(platform) = &kind.target {
+            ui.label(platform.to_string());
+        }
+    }
+    if tree.project.is_workspace_member(pkg.key) {
+        badge(
+            ui,
+            "member",
+            egui::Color32::DARK_BLUE,
+            egui::Color32::LIGHT_BLUE,
+        );
+    }
+    if cycle {
+        ui.label("(cycle)").on_hover_text("Already on this path");
+    }
+}
@@ -1,47 +1,391 @@
 use {
     crate::{
-        project::Project,
-        ui::{central_top_bar, Gui},
+        config::Config,
+        license::{LicensePolicy, LicenseReport, LicenseStatus, PkgLicense, POLICY_FILENAME},
+        notices::{Notices, NoticesFormat, PkgDir},
+        project::{PkgKey, Project},
+        ui::{
+            central_top_bar, dim_off_target,
+            widgets::{license_mismatch_label, license_status_badge},
+            Gui,
+        },
     },
     eframe::egui,
-    std::collections::hash_map::Entry,
+    std::{
+        path::{Path, PathBuf},
+        sync::mpsc,
+    },
 };
 
-fn compute_license_hashmap(project: &mut Project) {
-    for (key, pkg) in &project.packages {
-        let license = pkg.cm_pkg.license.clone().unwrap_or_else(|| "<no license>".into());
-        match project.license_map.entry(license) {
-            Entry::Occupied(mut occupied) => {
-                occupied.get_mut().push(key);
-            }
-            Entry::Vacant(vacant) => {
-                vacant.insert(vec![key]);
-            }
+/// The license policy file of a project, and what was loaded from it
+struct PolicyFile {
+    path: PathBuf,
+    /// `None` if there is no policy file
+    policy: Option<LicensePolicy>,
+    error: Option<String>,
+}
+
+impl PolicyFile {
+    fn load(path: PathBuf) -> Self {
+        if !path.exists() {
+            return Self {
+                path,
+                policy: None,
+                error: None,
+            };
+        }
+        match LicensePolicy::load(&path) {
+            Ok(policy) => Self {
+                path,
+                policy: Some(policy),
+                error: None,
+            },
+            Err(e) => Self {
+                path,
+                policy: None,
+                error: Some(format!("{e:#}")),
+            },
         }
     }
 }
 
-pub(crate) fn licenses_ui(ui: &mut egui::Ui, gui: &mut Gui, project: &mut Project) {
+/// State of the licenses tab
+#[derive(Default)]
+pub struct LicensesState {
+    /// Group by individual license identifier, instead of by whole expression
+    by_identifier: bool,
+    /// Only show packages with this status
+    status_filter: Option<LicenseStatus>,
+    /// Only show packages whose license files don't match their declared license
+    mismatches_only: bool,
+    policy: Option<PolicyFile>,
+    report: Option<LicenseReport>,
+    /// The report being built on a separate thread
+    report_recv: Option<mpsc::Receiver<LicenseReport>>,
+    notices: Option<Notices>,
+    /// The notices being collected on a separate thread
+    notices_recv: Option<mpsc::Receiver<Notices>>,
+    notices_format: NoticesFormat,
+    /// Outcome of the last attempt to save the notices
+    notices_result: Option<Result<String, String>>,
+}
+
+impl LicensesState {
+    /// Forget the computed report, e.g. because the project was reloaded
+    pub fn invalidate(&mut self) {
+        self.report = None;
+        self.report_recv = None;
+        self.notices = None;
+        self.notices_recv = None;
+    }
+}
+
+/// Run `compute` on a separate thread, which requests a repaint when it's done
+fn spawn<T: Send + 'static>(
+    ctx: &egui::Context,
+    compute: impl FnOnce() -> T + Send + 'static,
+) -> mpsc::Receiver<T> {
+    let (tx, rx) = mpsc::channel();
+    let ctx = ctx.clone();
+    std::thread::spawn(move || {
+        // Nobody is listening anymore if the project was reloaded meanwhile
+        let _ = tx.send(compute());
+        ctx.request_repaint();
+    });
+    rx
+}
+
+pub(crate) fn licenses_ui(ui: &mut egui::Ui, gui: &mut Gui, project: &Project, cfg: &mut Config) {
     central_top_bar(ui, gui, project);
-    if project.license_map.is_empty() {
-        compute_license_hashmap(project);
+    let root = project.workspace_root.clone().into_std_path_buf();
+    let policy_path = cfg
+        .license_policies
+        .get(&root)
+        .cloned()
+        .unwrap_or_else(|| root.join(POLICY_FILENAME));
+    let state = &mut gui.nav.licenses;
+    let mut selected = None;
+    if state.policy.as_ref().is_none_or(|file| file.path != policy_path) {
+        state.policy = Some(PolicyFile::load(policy_path.clone()));
+        state.report = None;
+        state.report_recv = None;
+    }
+    ui.horizontal(|ui| {
+        ui.label("Policy:");
+        ui.label(policy_path.display().to_string());
+        if ui.button("Choose...").clicked() {
+            if let Some(path) = rfd::FileDialog::new()
+                .add_filter("License policy", &["toml"])
+                .set_directory(&root)
+                .pick_file()
+            {
+                cfg.license_policies.insert(root.clone(), path);
+            }
+        }
+        if cfg.license_policies.contains_key(&root) && ui.button("Use default").clicked() {
+            cfg.license_policies.remove(&root);
+        }
+        if ui.button("Reload").clicked() {
+            state.policy = None;
+        }
+    });
+    egui::CollapsingHeader::new("Third-party notices").show(ui, |ui| {
+        notices_ui(ui, state, project, cfg, &root, &mut selected);
+    });
+    let Some(policy_file) = &state.policy else {
+        return;
+    };
+    if let Some(error) = &policy_file.error {
+        ui.colored_label(egui::Color32::RED, error);
+    } else if policy_file.policy.is_none() {
+        ui.label(format!(
+            "No policy file. Create {POLICY_FILENAME} in the workspace root with `allow`, `deny` \
+             and `review` lists of licenses, and per-crate `exceptions`, to check the licenses."
+        ));
+    }
+    if state.report.is_none() && state.report_recv.is_none() {
+        let packages: Vec<PkgDir> = project.packages.values().map(PkgDir::of).collect();
+        let policy = policy_file.policy.clone();
+        state.report_recv = Some(spawn(ui.ctx(), move || {
+            LicenseReport::new(&packages, policy.as_ref())
+        }));
     }
+    if let Some(report) = state.report_recv.as_ref().and_then(|recv| recv.try_recv().ok()) {
+        state.report = Some(report);
+        state.report_recv = None;
+    }
+    let Some(report) = &state.report else {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label("Reading the license files...");
+        });
+        return;
+    };
+    ui.horizontal(|ui| {
+        ui.selectable_value(&mut state.by_identifier, false, "By expression");
+        ui.selectable_value(&mut state.by_identifier, true, "By license");
+        ui.separator();
+        let mismatches = report
+            .files
+            .iter()
+            .filter(|(key, files)| {
+                files.mismatch.is_some()
+                    && (!gui.hide_off_target || project.packages[**key].on_target)
+            })
+            .count();
+        ui.checkbox(
+            &mut state.mismatches_only,
+            format!("License file mismatches ({mismatches})"),
+        )
+        .on_hover_text("The license files are compared with a set of known license texts");
+        if report.statuses.is_empty() {
+            return;
+        }
+        ui.separator();
+        ui.selectable_value(&mut state.status_filter, None, "All");
+        for status in LicenseStatus::ALL {
+            let count = report
+                .statuses
+                .iter()
+                .filter(|(key, pkg_status)| {
+                    **pkg_status == status
+                        && (!gui.hide_off_target || project.packages[**key].on_target)
+                })
+                .count();
+            ui.selectable_value(
+                &mut state.status_filter,
+                Some(status),
+                format!("{} ({count})", status.name()),
+            );
+        }
+    });
+    ui.separator();
+    let groups = if state.by_identifier {
+        &report.by_identifier
+    } else {
+        &report.by_expression
+    };
     egui::ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
         ui.style_mut().visuals.widgets.inactive.fg_stroke =
             egui::Stroke::new(2.0, egui::Color32::BLACK);
-        for (license, pkgs) in &project.license_map {
-            ui.collapsing(format!("{license} ({})", pkgs.len()), |ui| {
-                for pkg in pkgs {
-                    let selected = gui.secondary_pkg == Some(*pkg);
-                    let cm_pkg = &project.packages[*pkg].cm_pkg;
-                    if ui
-                        .selectable_label(selected, format!("{} {}", cm_pkg.name, cm_pkg.version))
-                        .clicked()
-                    {
-                        gui.secondary_pkg = Some(*pkg);
+        for (name, keys) in groups {
+            let keys: Vec<PkgKey> = keys
+                .iter()
+                .copied()
+                .filter(|key| !gui.hide_off_target || project.packages[*key].on_target)
+                .filter(|key| {
+                    state
+                        .status_filter
+                        .is_none_or(|filter| report.statuses.get(key) == Some(&filter))
+                })
+                .filter(|key| !state.mismatches_only || report.files[key].mismatch.is_some())
+                .collect();
+            if keys.is_empty() {
+                continue;
+            }
+            egui::CollapsingHeader::new(format!("{name} ({})", keys.len()))
+                .id_salt(("license_group", state.by_identifier, name))
+                .show(ui, |ui| {
+                    for key in keys {
+                        let pkg = &project.packages[key];
+                        ui.horizontal(|ui| {
+                            dim_off_target(ui, pkg.on_target);
+                            if let Some(status) = report.statuses.get(&key) {
+                                license_status_badge(ui, *status);
+                            }
+                            if ui
+                                .selectable_label(
+                                    gui.nav.secondary_pkg == Some(key),
+                                    format!("{} {}", pkg.cm_pkg.name, pkg.cm_pkg.version),
+                                )
+                                .clicked()
+                            {
+                                selected = Some(key);
+                            }
+                            match &report.licenses[&key] {
+                                PkgLicense::Expr(expr) if state.by_identifier => {
+                                    ui.weak(expr.to_string());
+                                }
+                                PkgLicense::Invalid { error, .. } => {
+                                    ui.colored_label(egui::Color32::RED, "invalid expression")
+                                        .on_hover_text(error);
+                                }
+                                PkgLicense::File(path) => {
+                                    ui.weak(path.as_str());
+                                }
+                                _ => {}
+                            }
+                            license_mismatch_label(ui, &report.files[&key]);
+                        });
                     }
+                });
+        }
+    });
+    if let Some(key) = selected {
+        gui.nav.secondary_pkg = Some(key);
+        gui.show_sidebar = true;
+    }
+}
+
+/// Rendering a notices document from the license files of the dependencies
+fn notices_ui(
+    ui: &mut egui::Ui,
+    state: &mut LicensesState,
+    project: &Project,
+    cfg: &mut Config,
+    root: &Path,
+    selected: &mut Option<PkgKey>,
+) {
+    let template_path = cfg.notices_templates.get(root).cloned();
+    ui.horizontal(|ui| {
+        ui.label("Template:");
+        match &template_path {
+            Some(path) => ui.label(path.display().to_string()),
+            None => ui.label("built-in"),
+        };
+        if ui.button("Choose...").clicked() {
+            if let Some(path) = rfd::FileDialog::new()
+                .add_filter("Template", &["md", "html", "htm", "txt"])
+                .set_directory(root)
+                .pick_file()
+            {
+                state.notices_format = NoticesFormat::of_path(&path);
+                cfg.notices_templates.insert(root.to_owned(), path);
+            }
+        }
+        if template_path.is_some() && ui.button("Use built-in").clicked() {
+            cfg.notices_templates.remove(root);
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Format:");
+        for format in NoticesFormat::ALL {
+            ui.selectable_value(&mut state.notices_format, format, format.name());
+        }
+        if ui.button("Rescan").on_hover_text("Read the license files again").clicked() {
+            state.notices = None;
+            state.notices_recv = None;
+        }
+    });
+    if state.notices.is_none() && state.notices_recv.is_none() {
+        let packages = Notices::shipped_packages(project);
+        state.notices_recv = Some(spawn(ui.ctx(), move || Notices::collect(&packages)));
+    }
+    if let Some(notices) = state.notices_recv.as_ref().and_then(|recv| recv.try_recv().ok()) {
+        state.notices = Some(notices);
+        state.notices_recv = None;
+    }
+    let Some(notices) = &state.notices else {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label("Reading the license files...");
+        });
+        return;
+    };
+    ui.label(format!(
+        "{} packages with license files, {} different license texts",
+        notices.packages.len(),
+        notices.texts.len()
+    ));
+    if ui.button("Save notices...").clicked() {
+        let format = state.notices_format;
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter(format.name(), &[format.extension()])
+            .set_directory(root)
+            .set_file_name(format!("THIRD_PARTY_NOTICES.{}", format.extension()))
+            .save_file()
+        {
+            state.notices_result = Some(
+                save_notices(notices, project, template_path.as_deref(), format, &path)
+                    .map(|()| format!("Saved {}", path.display()))
+                    .map_err(|e| format!("{e:#}")),
+            );
+        }
+    }
+    match &state.notices_result {
+        Some(Ok(msg)) => {
+            ui.label(msg);
+        }
+        Some(Err(e)) => {
+            ui.colored_label(egui::Color32::RED, e);
+        }
+        None => {}
+    }
+    if notices.missing.is_empty() {
+        return;
+    }
+    egui::CollapsingHeader::new(format!(
+        "No license file found for {} packages",
+        notices.missing.len()
+    ))
+    .id_salt("notices_missing")
+    .show(ui, |ui| {
+        for key in &notices.missing {
+            let pkg = &project.packages[*key];
+            ui.horizontal(|ui| {
+                if ui
+                    .selectable_label(false, format!("{} {}", pkg.cm_pkg.name, pkg.cm_pkg.version))
+                    .clicked()
+                {
+                    *selected = Some(*key);
                 }
+                ui.weak(PkgLicense::of(&pkg.cm_pkg).group_name());
             });
         }
     });
 }
+
+fn save_notices(
+    notices: &Notices,
+    project: &Project,
+    template_path: Option<&Path>,
+    format: NoticesFormat,
+    path: &Path,
+) -> anyhow::Result<()> {
+    let template = match template_path {
+        Some(template_path) => std::fs::read_to_string(template_path)?,
+        None => format.builtin_template().to_owned(),
+    };
+    let out = notices.render(project, &template, format)?;
+    Ok(std::fs::write(path, out)?)
+}
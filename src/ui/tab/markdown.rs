@@ -15,6 +15,6 @@ pub(crate) fn markdown_ui(ui: &mut egui::Ui, gui: &mut Gui, project: &Project) {
             // Hack to make things more legible
             ui.style_mut().visuals = egui::Visuals::light();
         }
-        CommonMarkViewer::new().show(ui, &mut gui.cm_cache, &gui.md.md);
+        CommonMarkViewer::new().show(ui, &mut gui.cm_cache, &gui.nav.md.md);
     });
 }
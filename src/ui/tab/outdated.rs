@@ -0,0 +1,217 @@
+use {
+    crate::{
+        project::{PkgKey, Project},
+        registry_index::VersionStatus,
+        ui::{central_top_bar, dim_off_target, widgets::yanked_badge, Gui},
+    },
+    eframe::egui,
+    egui_extras::{Column, TableBuilder},
+    std::{collections::HashMap, sync::mpsc},
+};
+
+type ScanResult = (HashMap<PkgKey, VersionStatus>, Vec<String>);
+
+/// Version status of every registry package, read from the index cache on a separate thread
+#[derive(Default)]
+pub struct VersionScan {
+    recv: Option<mpsc::Receiver<ScanResult>>,
+    pub statuses: Option<HashMap<PkgKey, VersionStatus>>,
+    /// Index entries that couldn't be read, with the reason
+    errors: Vec<String>,
+}
+
+impl VersionScan {
+    /// Start reading the index cache unless that already happened, and pick up the result
+    pub fn update(&mut self, project: &Project, ctx: &egui::Context) {
+        if let Some(recv) = &self.recv {
+            if let Ok((statuses, errors)) = recv.try_recv() {
+                self.statuses = Some(statuses);
+                self.errors = errors;
+                self.recv = None;
+            }
+            return;
+        }
+        if self.statuses.is_some() {
+            return;
+        }
+        let packages: Vec<_> =
+            project.packages.iter().map(|(key, pkg)| (key, pkg.cm_pkg.clone())).collect();
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let mut statuses = HashMap::new();
+            let mut errors = Vec::new();
+            for (key, pkg) in packages {
+                match VersionStatus::of(&pkg) {
+                    Ok(Some(status)) => {
+                        statuses.insert(key, status);
+                    }
+                    Ok(None) => {}
+                    Err(e) => errors.push(format!("{e:#}")),
+                }
+            }
+            // Nobody is listening anymore if the project was reloaded meanwhile
+            let _ = tx.send((statuses, errors));
+            ctx.request_repaint();
+        });
+        self.recv = Some(rx);
+    }
+    /// Forget the statuses, e.g. because the project was reloaded
+    pub fn invalidate(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Default)]
+enum Filter {
+    #[default]
+    All,
+    Updates,
+    Upgrades,
+    Yanked,
+}
+
+/// State of the outdated tab
+#[derive(Default)]
+pub struct OutdatedState {
+    filter: Filter,
+}
+
+/// The newest versions of the registry packages, and which of them are yanked
+pub(crate) fn outdated_ui(ui: &mut egui::Ui, gui: &mut Gui, project: &Project) {
+    central_top_bar(ui, gui, project);
+    gui.nav.versions.update(project, ui.ctx());
+    let Some(statuses) = &gui.nav.versions.statuses else {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label("Reading the registry index cache...");
+        });
+        return;
+    };
+    let mut keys: Vec<PkgKey> = statuses
+        .keys()
+        .copied()
+        .filter(|key| !gui.hide_off_target || project.packages[*key].on_target)
+        .collect();
+    keys.sort_by(|a, b| {
+        let (a, b) = (&project.packages[*a].cm_pkg, &project.packages[*b].cm_pkg);
+        (&a.name, &a.version).cmp(&(&b.name, &b.version))
+    });
+    let count = |filter: Filter| {
+        keys.iter()
+            .filter(|key| matches_filter(filter, &statuses[*key], project, **key))
+            .count()
+    };
+    let errors = &gui.nav.versions.errors;
+    let state = &mut gui.nav.outdated;
+    let mut rescan = false;
+    ui.horizontal(|ui| {
+        for (filter, name) in [
+            (Filter::All, "All"),
+            (Filter::Updates, "Compatible updates"),
+            (Filter::Upgrades, "Newer major versions"),
+            (Filter::Yanked, "Yanked"),
+        ] {
+            ui.selectable_value(
+                &mut state.filter,
+                filter,
+                format!("{name} ({})", count(filter)),
+            );
+        }
+        if ui
+            .button("Rescan")
+            .on_hover_text("Read the index cache again, e.g. after `cargo update`")
+            .clicked()
+        {
+            rescan = true;
+        }
+        if !errors.is_empty() {
+            ui.colored_label(
+                egui::Color32::YELLOW,
+                format!("{} index entries could not be read", errors.len()),
+            )
+            .on_hover_text(errors.join("\n"));
+        }
+    });
+    ui.label(
+        "As of the last time cargo updated its index cache. Nothing is fetched from the network.",
+    );
+    ui.separator();
+    keys.retain(|key| matches_filter(state.filter, &statuses[key], project, *key));
+    let mut selected = None;
+    TableBuilder::new(ui)
+        .striped(true)
+        .column(Column::auto().at_least(160.0))
+        .columns(Column::auto().at_least(100.0), 3)
+        .column(Column::remainder())
+        .header(22.0, |mut row| {
+            for title in ["Package", "Current", "Compatible", "Newest", ""] {
+                row.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(22.0, keys.len(), |mut row| {
+                let key = keys[row.index()];
+                let pkg = &project.packages[key];
+                let status = &statuses[&key];
+                let current = &pkg.cm_pkg.version;
+                row.col(|ui| {
+                    dim_off_target(ui, pkg.on_target);
+                    if ui
+                        .selectable_label(
+                            gui.nav.secondary_pkg == Some(key),
+                            egui::RichText::new(&pkg.cm_pkg.name)
+                                .color(gui.style.colors.highlighted_text),
+                        )
+                        .clicked()
+                    {
+                        selected = Some(key);
+                    }
+                });
+                row.col(|ui| {
+                    ui.label(current.to_string());
+                });
+                row.col(|ui| {
+                    match &status.newest_compatible {
+                        Some(version) if status.update_available(current) => {
+                            ui.strong(version.to_string())
+                        }
+                        Some(version) => ui.weak(version.to_string()),
+                        None => ui.weak("-"),
+                    };
+                });
+                row.col(|ui| {
+                    match &status.newest {
+                        Some(version) if status.upgrade_available() => {
+                            ui.strong(version.to_string())
+                        }
+                        Some(version) => ui.weak(version.to_string()),
+                        None => ui.weak("-"),
+                    };
+                });
+                row.col(|ui| {
+                    if status.yanked {
+                        yanked_badge(ui);
+                    }
+                });
+            });
+        });
+    if rescan {
+        gui.nav.versions.invalidate();
+    }
+    if let Some(key) = selected {
+        gui.nav.secondary_pkg = Some(key);
+        gui.show_sidebar = true;
+    }
+}
+
+fn matches_filter(filter: Filter, status: &VersionStatus, project: &Project, key: PkgKey) -> bool {
+    match filter {
+        Filter::All => true,
+        Filter::Updates => status.update_available(&project.packages[key].cm_pkg.version),
+        Filter::Upgrades => status.upgrade_available(),
+        Filter::Yanked => status.yanked,
+    }
+}
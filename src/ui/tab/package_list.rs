@@ -2,28 +2,36 @@ use {
     super::Tab,
     crate::{
         project::Project,
-        ui::{central_top_bar, widgets::VersionBadge, Gui, PkgFilter},
+        ui::{
+            central_top_bar, dim_off_target,
+            widgets::{advisory_badges, yanked_badge, VersionBadge},
+            Gui, PkgFilter,
+        },
     },
     eframe::egui,
 };
 
 pub(crate) fn package_list_ui(project: &Project, ui: &mut egui::Ui, gui: &mut Gui) {
     central_top_bar(ui, gui, project);
+    gui.nav.versions.update(project, ui.ctx());
     let mut filtered: Vec<_> = project.packages.keys().collect();
     ui.horizontal(|ui| {
         if ui
             .add(
-                egui::TextEdit::singleline(&mut gui.pkg_list_filter_string)
+                egui::TextEdit::singleline(&mut gui.nav.pkg_list_filter_string)
                     .text_color(gui.style.colors.text_edit_text)
                     .hint_text("Filter"),
             )
             .changed()
         {
-            gui.pkg_list_compiled_filter = PkgFilter::from_str(&gui.pkg_list_filter_string);
+            gui.nav.pkg_list_compiled_filter = PkgFilter::from_str(&gui.nav.pkg_list_filter_string);
         }
         filtered.retain(|key| {
             let pkg = &project.packages[*key];
-            match &gui.pkg_list_compiled_filter {
+            if gui.hide_off_target && !pkg.on_target {
+                return false;
+            }
+            match &gui.nav.pkg_list_compiled_filter {
                 Some(filt) => filt.matches(pkg),
                 None => true,
             }
@@ -44,24 +52,52 @@ pub(crate) fn package_list_ui(project: &Project, ui: &mut egui::Ui, gui: &mut Gu
                 for &key in &filtered[range] {
                     let pkg = &project.packages[key];
                     ui.scope(|ui| {
+                        dim_off_target(ui, pkg.on_target);
                         let re = ui.selectable_label(
-                            gui.secondary_pkg == Some(key),
+                            gui.nav.secondary_pkg == Some(key),
                             egui::RichText::new(&pkg.cm_pkg.name)
                                 .color(gui.style.colors.highlighted_text),
                         );
                         if re.clicked() {
-                            gui.secondary_pkg = Some(key);
+                            gui.nav.secondary_pkg = Some(key);
                         }
                         if re.double_clicked() {
-                            gui.primary_pkg = Some(key);
-                            gui.secondary_pkg = None;
-                            gui.tab = Tab::ViewSingle;
+                            gui.nav.primary_pkg = Some(key);
+                            gui.nav.secondary_pkg = None;
+                            gui.nav.tab = Tab::ViewSingle;
                         }
+                        re.context_menu(|ui| {
+                            if ui.button("Focus").clicked() {
+                                gui.nav.primary_pkg = Some(key);
+                                gui.nav.tab = Tab::ViewSingle;
+                                ui.close_menu();
+                            }
+                            if ui.button("Why is this here?").clicked() {
+                                gui.nav.inverse_pkg = Some(key);
+                                gui.nav.tab = Tab::Inverse;
+                                ui.close_menu();
+                            }
+                        });
                         ui.add(VersionBadge::new(&pkg.cm_pkg.version, &gui.style));
+                        if let Some(db) = &gui.advisory_db.db {
+                            advisory_badges(ui, &db.for_pkg(pkg));
+                        }
+                        let status = gui
+                            .nav
+                            .versions
+                            .statuses
+                            .as_ref()
+                            .and_then(|statuses| statuses.get(&key));
+                        if status.is_some_and(|status| status.yanked) {
+                            yanked_badge(ui);
+                        }
                     });
                     if let Some(info) = &pkg.cm_pkg.description {
                         if let Some(fst_line) = info.lines().next() {
-                            ui.label(fst_line).on_hover_text(info);
+                            ui.scope(|ui| {
+                                dim_off_target(ui, pkg.on_target);
+                                ui.label(fst_line).on_hover_text(info);
+                            });
                         }
                     }
                     ui.end_row();
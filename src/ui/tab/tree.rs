@@ -0,0 +1,448 @@
+use {
+    super::Tab,
+    crate::{
+        project::{DepLink, PkgKey, Project},
+        ui::{
+            central_top_bar, dim_off_target,
+            widgets::{DepkindBadge, VersionBadge},
+            Gui,
+        },
+    },
+    cargo_metadata::DependencyKind,
+    eframe::egui,
+    std::collections::{hash_map::Entry, HashMap, HashSet},
+};
+
+const ROW_HEIGHT: f32 = 22.0;
+
+/// State of the dependency tree tab
+pub struct TreeState {
+    /// Package the tree starts at. `None` means the project's initial package.
+    root: Option<PkgKey>,
+    /// Packages whose dependencies are shown
+    expanded: HashSet<PkgKey>,
+    show_normal: bool,
+    show_build: bool,
+    show_dev: bool,
+    /// Maximum depth to expand to. `None` means unlimited.
+    depth_limit: Option<usize>,
+    show_features: bool,
+    search: String,
+    /// Scroll to this package on the next frame
+    scroll_to: Option<PkgKey>,
+    layout: Option<Layout>,
+}
+
+/// Everything the shape of the tree depends on, besides which packages are expanded
+#[derive(Clone, PartialEq)]
+struct Filters {
+    root: PkgKey,
+    show_normal: bool,
+    show_build: bool,
+    show_dev: bool,
+    depth_limit: Option<usize>,
+    hide_off_target: bool,
+    target: Option<String>,
+}
+
+/// The tree, computed once for the current filters
+struct Layout {
+    filters: Filters,
+    /// Parent and depth of the first occurrence of every package, see [`first_occurrences`]
+    first: HashMap<PkgKey, (Option<PkgKey>, usize)>,
+    /// The expanded part of the tree. `None` if the expanded packages changed.
+    rows: Option<Vec<Row>>,
+}
+
+impl Default for TreeState {
+    fn default() -> Self {
+        Self {
+            root: None,
+            expanded: HashSet::new(),
+            show_normal: true,
+            show_build: true,
+            show_dev: true,
+            depth_limit: None,
+            show_features: false,
+            search: String::new(),
+            scroll_to: None,
+            layout: None,
+        }
+    }
+}
+
+impl TreeState {
+    /// Show the tree starting from `root`
+    pub fn set_root(&mut self, root: PkgKey) {
+        self.root = Some(root);
+        self.expanded = HashSet::from([root]);
+        self.expansion_changed();
+    }
+    /// Point the state at the corresponding packages of a reloaded project
+    pub fn remap(&mut self, remap: impl Fn(PkgKey) -> Option<PkgKey>) {
+        self.root = self.root.and_then(&remap);
+        self.expanded = self.expanded.drain().filter_map(&remap).collect();
+        self.scroll_to = None;
+        self.layout = None;
+    }
+    fn expansion_changed(&mut self) {
+        if let Some(layout) = &mut self.layout {
+            layout.rows = None;
+        }
+    }
+    fn filters(&self, root: PkgKey, hide_off_target: bool, project: &Project) -> Filters {
+        Filters {
+            root,
+            show_normal: self.show_normal,
+            show_build: self.show_build,
+            show_dev: self.show_dev,
+            depth_limit: self.depth_limit,
+            hide_off_target,
+            target: project.target.as_ref().map(|target| target.triple.clone()),
+        }
+    }
+    fn follows(&self, link: &DepLink) -> bool {
+        // Links of unknown kind (with `--lockfile-only`) count as normal
+        if link.kinds.is_empty() {
+            return self.show_normal;
+        }
+        link.kinds.iter().any(|kind| match kind.kind {
+            DependencyKind::Build => self.show_build,
+            DependencyKind::Development => self.show_dev,
+            _ => self.show_normal,
+        })
+    }
+}
+
+/// A visible row of the tree
+struct Row {
+    depth: usize,
+    key: PkgKey,
+    /// Index of the edge from the parent in its dependencies. `None` for the root.
+    link: Option<usize>,
+    parent: Option<PkgKey>,
+    /// Whether the dependencies of this package are shown here, rather than at an earlier row
+    canonical: bool,
+    has_children: bool,
+}
+
+/// Indices of the dependencies of `key` that pass the filters, ordered by name and version
+fn children(
+    project: &Project,
+    state: &TreeState,
+    hide_off_target: bool,
+    key: PkgKey,
+) -> Vec<usize> {
+    let links = &project.packages[key].dependencies;
+    let mut indices: Vec<usize> = (0..links.len())
+        .filter(|idx| {
+            let link = &links[*idx];
+            state.follows(link) && (!hide_off_target || project.link_on_target(link))
+        })
+        .collect();
+    indices.sort_by(|a, b| {
+        let (a, b) = (
+            &project.packages[links[*a].pkg_key].cm_pkg,
+            &project.packages[links[*b].pkg_key].cm_pkg,
+        );
+        (&a.name, &a.version).cmp(&(&b.name, &b.version))
+    });
+    indices
+}
+
+/// Like `cargo tree`, the dependencies of a package are only listed at its first occurrence
+/// in the fully expanded tree, in the order the rows are shown.
+///
+/// Returns the parent of that first occurrence for every package in the tree,
+/// and the depth it's at.
+fn first_occurrences(
+    project: &Project,
+    state: &TreeState,
+    hide_off_target: bool,
+    root: PkgKey,
+) -> HashMap<PkgKey, (Option<PkgKey>, usize)> {
+    let mut first = HashMap::from([(root, (None, 0))]);
+    let mut stack = vec![(
+        root,
+        0,
+        children(project, state, hide_off_target, root).into_iter(),
+    )];
+    while let Some((parent, depth, links)) = stack.last_mut() {
+        let (parent, depth) = (*parent, *depth);
+        let Some(idx) = links.next() else {
+            stack.pop();
+            continue;
+        };
+        let key = project.packages[parent].dependencies[idx].pkg_key;
+        if let Entry::Vacant(entry) = first.entry(key) {
+            entry.insert((Some(parent), depth + 1));
+            if state.depth_limit.is_none_or(|limit| depth + 1 < limit) {
+                stack.push((
+                    key,
+                    depth + 1,
+                    children(project, state, hide_off_target, key).into_iter(),
+                ));
+            }
+        }
+    }
+    first
+}
+
+pub(crate) fn tree_ui(ui: &mut egui::Ui, gui: &mut Gui, project: &Project) {
+    central_top_bar(ui, gui, project);
+    let Some(root) = gui
+        .nav
+        .tree
+        .root
+        .filter(|key| project.packages.contains_key(*key))
+        .or_else(|| project.initial_pkg())
+    else {
+        ui.label("No package to show the tree of");
+        return;
+    };
+    if gui.nav.tree.root != Some(root) {
+        gui.nav.tree.set_root(root);
+    }
+    let state = &mut gui.nav.tree;
+    let mut search_changed = false;
+    ui.horizontal_wrapped(|ui| {
+        ui.label("Edges");
+        ui.checkbox(&mut state.show_normal, "normal");
+        ui.checkbox(&mut state.show_build, "build");
+        ui.checkbox(&mut state.show_dev, "dev");
+        ui.separator();
+        let mut limited = state.depth_limit.is_some();
+        ui.checkbox(&mut limited, "Depth limit");
+        let mut limit = state.depth_limit.unwrap_or(3);
+        ui.add_enabled(limited, egui::DragValue::new(&mut limit).range(1..=100));
+        state.depth_limit = limited.then_some(limit);
+        ui.separator();
+        ui.checkbox(&mut state.show_features, "Show features on edges");
+        ui.separator();
+        search_changed = ui
+            .add(
+                egui::TextEdit::singleline(&mut state.search)
+                    .hint_text("Search")
+                    .desired_width(150.0)
+                    .text_color(gui.style.colors.text_edit_text),
+            )
+            .changed();
+        if ui.button("Collapse all").clicked() {
+            state.expanded = HashSet::from([root]);
+            state.expansion_changed();
+        }
+    });
+    let filters = state.filters(root, gui.hide_off_target, project);
+    let mut layout = match state.layout.take() {
+        Some(layout) if layout.filters == filters => layout,
+        _ => Layout {
+            first: first_occurrences(project, state, gui.hide_off_target, root),
+            filters,
+            rows: None,
+        },
+    };
+    let first = &layout.first;
+    let query = state.search.trim().to_ascii_lowercase();
+    let matches = |key: PkgKey| {
+        !query.is_empty() && project.packages[key].cm_pkg.name.to_ascii_lowercase().contains(&query)
+    };
+    let match_count = first.keys().filter(|key| matches(**key)).count();
+    if search_changed && match_count != 0 {
+        // Expand the way to every match
+        let mut matched: Vec<PkgKey> = first.keys().copied().filter(|key| matches(*key)).collect();
+        matched.sort_by_key(|key| first[key].1);
+        state.scroll_to = matched.first().copied();
+        for key in matched {
+            let mut parent = first[&key].0;
+            while let Some(key) = parent {
+                state.expanded.insert(key);
+                parent = first[&key].0;
+            }
+        }
+        layout.rows = None;
+    }
+    if !query.is_empty() {
+        ui.label(format!("{match_count} matching packages"));
+    }
+    if layout.rows.is_none() {
+        layout.rows = Some(visible_rows(
+            project,
+            state,
+            gui.hide_off_target,
+            root,
+            &layout.first,
+        ));
+    }
+    let scroll_to = state.scroll_to.take();
+    state.layout = Some(layout);
+    let Some(Layout {
+        rows: Some(rows), ..
+    }) = &gui.nav.tree.layout
+    else {
+        return;
+    };
+    ui.separator();
+    let mut scroll = egui::ScrollArea::vertical().auto_shrink(false);
+    if let Some(target) = scroll_to {
+        if let Some(idx) = rows.iter().position(|row| row.key == target && row.canonical) {
+            let row_height = ROW_HEIGHT + ui.spacing().item_spacing.y;
+            scroll = scroll.vertical_scroll_offset(idx as f32 * row_height);
+        }
+    }
+    let mut toggle = None;
+    let mut action = None;
+    scroll.show_rows(ui, ROW_HEIGHT, rows.len(), |ui, range| {
+        for row in &rows[range] {
+            ui.horizontal(|ui| {
+                ui.set_height(ROW_HEIGHT);
+                ui.add_space(row.depth as f32 * ui.spacing().indent);
+                let pkg = &project.packages[row.key];
+                let link = row
+                    .parent
+                    .zip(row.link)
+                    .map(|(parent, idx)| &project.packages[parent].dependencies[idx]);
+                if let Some(link) = link {
+                    dim_off_target(ui, project.link_on_target(link));
+                }
+                if row.canonical && row.has_children {
+                    let expanded = gui.nav.tree.expanded.contains(&row.key);
+                    if ui.small_button(if expanded { "⏷" } else { "⏵" }).clicked() {
+                        toggle = Some(row.key);
+                    }
+                } else {
+                    ui.add_space(ui.spacing().indent);
+                }
+                let mut text =
+                    egui::RichText::new(&pkg.cm_pkg.name).color(gui.style.colors.highlighted_text);
+                if matches(row.key) {
+                    text = text.strong().underline();
+                }
+                let re = ui.selectable_label(gui.nav.secondary_pkg == Some(row.key), text);
+                if re.clicked() {
+                    action = Some(TreeAction::Select(row.key));
+                }
+                if re.double_clicked() {
+                    action = Some(TreeAction::Focus(row.key));
+                }
+                re.context_menu(|ui| {
+                    if ui.button("Show tree from here").clicked() {
+                        action = Some(TreeAction::SetRoot(row.key));
+                        ui.close_menu();
+                    }
+                    if ui.button("Why is this here?").clicked() {
+                        action = Some(TreeAction::Inverse(row.key));
+                        ui.close_menu();
+                    }
+                });
+                ui.add(VersionBadge::new(&pkg.cm_pkg.version, &gui.style));
+                if let Some(link) = link {
+                    for kind in &link.kinds {
+                        if kind.kind != DependencyKind::Normal {
+                            ui.add(DepkindBadge::new(kind.kind, &gui.style));
+                        }
+                        if let Some(platform) = &kind.target {
+                            ui.label(platform.to_string());
+                        }
+                    }
+                }
+                if gui.nav.tree.show_features {
+                    if let Some(parent) = row.parent {
+                        let features = project.edge_features(parent, row.key, None);
+                        if !features.is_empty() {
+                            ui.label(egui::RichText::new(features.join(", ")).weak());
+                        }
+                    }
+                }
+                if !row.canonical && row.has_children {
+                    ui.label("(*)").on_hover_text("Dependencies already listed above");
+                }
+            });
+        }
+    });
+    if let Some(key) = toggle {
+        let state = &mut gui.nav.tree;
+        if !state.expanded.remove(&key) {
+            state.expanded.insert(key);
+        }
+        state.expansion_changed();
+    }
+    match action {
+        Some(TreeAction::Select(key)) => {
+            gui.nav.secondary_pkg = Some(key);
+            gui.show_sidebar = true;
+        }
+        Some(TreeAction::Focus(key)) => {
+            gui.nav.primary_pkg = Some(key);
+            gui.nav.tab = Tab::ViewSingle;
+        }
+        Some(TreeAction::SetRoot(key)) => gui.nav.tree.set_root(key),
+        Some(TreeAction::Inverse(key)) => {
+            gui.nav.inverse_pkg = Some(key);
+            gui.nav.tab = Tab::Inverse;
+        }
+        None => {}
+    }
+}
+
+enum TreeAction {
+    Select(PkgKey),
+    Focus(PkgKey),
+    SetRoot(PkgKey),
+    Inverse(PkgKey),
+}
+
+/// Flatten the expanded part of the tree into rows
+fn visible_rows(
+    project: &Project,
+    state: &TreeState,
+    hide_off_target: bool,
+    root: PkgKey,
+    first: &HashMap<PkgKey, (Option<PkgKey>, usize)>,
+) -> Vec<Row> {
+    let has_children = |key: PkgKey| first.values().any(|(parent, _)| *parent == Some(key));
+    let mut rows = vec![Row {
+        depth: 0,
+        key: root,
+        link: None,
+        parent: None,
+        canonical: true,
+        has_children: has_children(root),
+    }];
+    let mut stack = Vec::new();
+    if state.expanded.contains(&root) {
+        stack.push((
+            root,
+            0,
+            children(project, state, hide_off_target, root).into_iter(),
+        ));
+    }
+    while let Some((parent, depth, links)) = stack.last_mut() {
+        let (parent, depth) = (*parent, *depth);
+        let Some(link) = links.next() else {
+            stack.pop();
+            continue;
+        };
+        let key = project.packages[parent].dependencies[link].pkg_key;
+        let canonical = first.get(&key).is_some_and(|(first_parent, first_depth)| {
+            *first_parent == Some(parent) && *first_depth == depth + 1
+        });
+        let children_shown = state.depth_limit.is_none_or(|limit| depth + 1 < limit);
+        rows.push(Row {
+            depth: depth + 1,
+            key,
+            link: Some(link),
+            parent: Some(parent),
+            canonical,
+            has_children: children_shown
+                && !children(project, state, hide_off_target, key).is_empty(),
+        });
+        if canonical && state.expanded.contains(&key) {
+            stack.push((
+                key,
+                depth + 1,
+                children(project, state, hide_off_target, key).into_iter(),
+            ));
+        }
+    }
+    rows
+}
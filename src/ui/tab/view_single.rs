@@ -7,8 +7,13 @@ use {
     eframe::egui,
 };
 
-pub(crate) fn view_single_ui(ui: &mut egui::Ui, gui: &mut Gui, project: &Project, cfg: &Config) {
-    if let Some(id) = gui.primary_pkg {
+pub(crate) fn view_single_ui(
+    ui: &mut egui::Ui,
+    gui: &mut Gui,
+    project: &Project,
+    cfg: &mut Config,
+) {
+    if let Some(id) = gui.nav.primary_pkg {
         let pkg = &project.packages[id];
         package_ui(project, pkg, ui, gui, cfg);
     } else {
@@ -17,7 +22,7 @@ pub(crate) fn view_single_ui(ui: &mut egui::Ui, gui: &mut Gui, project: &Project
     }
 }
 
-fn package_ui(project: &Project, pkg: &Pkg, ui: &mut egui::Ui, gui: &mut Gui, cfg: &Config) {
+fn package_ui(project: &Project, pkg: &Pkg, ui: &mut egui::Ui, gui: &mut Gui, cfg: &mut Config) {
     central_top_bar(ui, gui, project);
-    pkg_info_ui(ui, pkg, &project.packages, gui, cfg);
+    pkg_info_ui(ui, pkg, project, gui, cfg, false);
 }
@@ -0,0 +1,135 @@
+use {
+    super::Tab,
+    crate::{
+        project::{DepLink, PkgKey, Project},
+        ui::{
+            central_top_bar, cheader, dim_off_target,
+            widgets::{badge, DepkindBadge, VersionBadge},
+            Gui,
+        },
+    },
+    eframe::egui,
+};
+
+pub(crate) fn workspace_ui(ui: &mut egui::Ui, gui: &mut Gui, project: &Project) {
+    central_top_bar(ui, gui, project);
+    ui.label(format!(
+        "{} workspace members",
+        project.workspace_members.len()
+    ));
+    ui.separator();
+    egui::ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
+        for &key in &project.workspace_members {
+            member_ui(ui, gui, project, key);
+        }
+    });
+}
+
+fn member_ui(ui: &mut egui::Ui, gui: &mut Gui, project: &Project, key: PkgKey) {
+    let pkg = &project.packages[key];
+    ui.horizontal(|ui| {
+        pkg_label_ui(ui, gui, key, project);
+        ui.add(VersionBadge::new(&pkg.cm_pkg.version, &gui.style));
+        match &pkg.cm_pkg.publish {
+            None => badge(
+                ui,
+                "publishable",
+                egui::Color32::DARK_GREEN,
+                egui::Color32::LIGHT_GREEN,
+            ),
+            Some(registries) if registries.is_empty() => badge(
+                ui,
+                "publish = false",
+                egui::Color32::from_rgb(115, 115, 115),
+                egui::Color32::WHITE,
+            ),
+            Some(registries) => badge(
+                ui,
+                "registry only",
+                egui::Color32::DARK_BLUE,
+                egui::Color32::LIGHT_BLUE,
+            )
+            .on_hover_text(registries.join(", ")),
+        };
+        if project.workspace_default_members.contains(&key)
+            && project.workspace_default_members.len() != project.workspace_members.len()
+        {
+            badge(
+                ui,
+                "default",
+                gui.style.colors.active_weak_bg_fill,
+                gui.style.colors.highlighted_text,
+            );
+        }
+    });
+    ui.indent(key, |ui| {
+        let (internal, external): (Vec<&DepLink>, Vec<&DepLink>) = pkg
+            .dependencies
+            .iter()
+            .partition(|link| project.is_workspace_member(link.pkg_key));
+        let used_by: Vec<&DepLink> = pkg
+            .dependents
+            .iter()
+            .filter(|link| project.is_workspace_member(link.pkg_key))
+            .collect();
+        let id = pkg.cm_pkg.name.as_str();
+        links_ui(ui, gui, project, id, "Workspace dependencies", &internal);
+        links_ui(ui, gui, project, id, "Used by members", &used_by);
+        links_ui(ui, gui, project, id, "Direct dependencies", &external);
+    });
+    ui.separator();
+}
+
+fn links_ui(
+    ui: &mut egui::Ui,
+    gui: &mut Gui,
+    project: &Project,
+    id: &str,
+    label: &str,
+    links: &[&DepLink],
+) {
+    if links.is_empty() {
+        return;
+    }
+    cheader(&format!("{label} ({})", links.len()), &gui.style)
+        .id_salt((id, label))
+        .show(ui, |ui| {
+            for link in links {
+                let on_target = project.link_on_target(link);
+                if gui.hide_off_target && !on_target {
+                    continue;
+                }
+                ui.horizontal(|ui| {
+                    dim_off_target(ui, on_target);
+                    pkg_label_ui(ui, gui, link.pkg_key, project);
+                    let dpkg = &project.packages[link.pkg_key];
+                    ui.add(VersionBadge::new(&dpkg.cm_pkg.version, &gui.style));
+                    for kind in &link.kinds {
+                        ui.add(DepkindBadge::new(kind.kind, &gui.style));
+                        if let Some(platform) = &kind.target {
+                            ui.label(platform.to_string());
+                        }
+                    }
+                });
+            }
+        });
+}
+
+fn pkg_label_ui(ui: &mut egui::Ui, gui: &mut Gui, key: PkgKey, project: &Project) {
+    let pkg = &project.packages[key];
+    let re = ui.selectable_label(
+        gui.nav.secondary_pkg == Some(key),
+        egui::RichText::new(&pkg.cm_pkg.name)
+            .color(gui.style.colors.highlighted_text)
+            .strong(),
+    );
+    if re.clicked() {
+        gui.nav.secondary_pkg = Some(key);
+        gui.show_sidebar = true;
+    }
+    if re.double_clicked() {
+        gui.nav.primary_pkg = Some(key);
+        gui.nav.secondary_pkg = None;
+        gui.nav.tab = Tab::ViewSingle;
+    }
+}
@@ -1,5 +1,11 @@
 use {
-    crate::style::Style,
+    crate::{
+        advisory::{Advisory, AdvisoryKind},
+        features::EdgeKind,
+        license::LicenseStatus,
+        license_text::LicenseFiles,
+        style::Style,
+    },
     cargo_metadata::{semver::Version, DependencyKind},
     eframe::egui,
 };
@@ -43,6 +49,62 @@ impl<'a> DepkindBadge<'a> {
     }
 }
 
+/// How a feature explorer node was turned on
+pub struct FeatureEdgeBadge<'s> {
+    kind: EdgeKind,
+    style: &'s Style,
+}
+
+impl egui::Widget for FeatureEdgeBadge<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let (text, bg_color, text_color, hover) = match self.kind {
+            EdgeKind::Feature => (
+                "feature",
+                self.style.colors.inactive_weak_bg_fill,
+                self.style.colors.inactive_fg_stroke,
+                "Feature of the same package",
+            ),
+            EdgeKind::OptionalDep => (
+                "dep:",
+                egui::Color32::from_rgb(25, 50, 100),
+                egui::Color32::YELLOW,
+                "Enables an optional dependency",
+            ),
+            EdgeKind::DepFeature => (
+                "dep/",
+                egui::Color32::from_rgb(70, 35, 90),
+                egui::Color32::YELLOW,
+                "Feature of a dependency",
+            ),
+            EdgeKind::WeakDepFeature => (
+                "weak ?/",
+                egui::Color32::from_rgb(90, 90, 90),
+                egui::Color32::LIGHT_GRAY,
+                "Feature of a dependency, only if the dependency is enabled by something else",
+            ),
+            EdgeKind::DepDeclaration => (
+                "declared",
+                egui::Color32::from_rgb(32, 60, 18),
+                egui::Color32::YELLOW,
+                "Requested by the dependency declaration",
+            ),
+            EdgeKind::RequiredDep => (
+                "required",
+                egui::Color32::from_rgb(60, 60, 30),
+                egui::Color32::YELLOW,
+                "Non-optional dependency of a package that is depended on",
+            ),
+        };
+        badge(ui, text, bg_color, text_color).on_hover_text(hover)
+    }
+}
+
+impl<'a> FeatureEdgeBadge<'a> {
+    pub fn new(kind: EdgeKind, style: &'a Style) -> Self {
+        Self { kind, style }
+    }
+}
+
 pub fn badge(
     ui: &mut egui::Ui,
     text: &str,
@@ -79,3 +141,72 @@ impl egui::Widget for VersionBadge<'_> {
         badge(ui, &self.ver.to_string(), self.bg_color, self.text_color)
     }
 }
+
+pub fn license_status_badge(ui: &mut egui::Ui, status: LicenseStatus) -> egui::Response {
+    let (bg_color, text_color) = match status {
+        LicenseStatus::Compliant => (egui::Color32::DARK_GREEN, egui::Color32::WHITE),
+        LicenseStatus::NeedsReview => (egui::Color32::from_rgb(120, 100, 20), egui::Color32::WHITE),
+        LicenseStatus::Unknown => (egui::Color32::DARK_GRAY, egui::Color32::WHITE),
+        LicenseStatus::Violating => (egui::Color32::DARK_RED, egui::Color32::WHITE),
+    };
+    badge(ui, status.name(), bg_color, text_color)
+}
+
+/// A warning about license files that don't match the declared license, with the
+/// recognized license of each file on hover
+pub fn license_mismatch_label(ui: &mut egui::Ui, files: &LicenseFiles) {
+    let Some(mismatch) = &files.mismatch else {
+        return;
+    };
+    let re = ui.colored_label(
+        egui::Color32::from_rgb(220, 140, 20),
+        format!("⚠ {mismatch}"),
+    );
+    if files.files.is_empty() {
+        return;
+    }
+    re.on_hover_ui(|ui| {
+        for file in &files.files {
+            match &file.recognized {
+                Some(m) => ui.label(format!(
+                    "{}: {} ({:.0}% similar)",
+                    file.name,
+                    m.ids.join(" and "),
+                    m.similarity * 100.0
+                )),
+                None => ui.label(format!("{}: not recognized", file.name)),
+            };
+        }
+    });
+}
+
+pub fn advisory_kind_badge(ui: &mut egui::Ui, kind: AdvisoryKind) -> egui::Response {
+    let bg_color = match kind {
+        AdvisoryKind::Vulnerability => egui::Color32::DARK_RED,
+        AdvisoryKind::Unsound => egui::Color32::from_rgb(150, 70, 0),
+        AdvisoryKind::Unmaintained | AdvisoryKind::Yanked | AdvisoryKind::Notice => {
+            egui::Color32::from_rgb(120, 100, 20)
+        }
+    };
+    badge(ui, kind.name(), bg_color, egui::Color32::WHITE)
+}
+
+pub fn yanked_badge(ui: &mut egui::Ui) -> egui::Response {
+    badge(ui, "yanked", egui::Color32::DARK_RED, egui::Color32::WHITE)
+        .on_hover_text("This version was yanked from the registry")
+}
+
+/// One badge per kind of advisory affecting a package, listing the advisories on hover
+pub fn advisory_badges(ui: &mut egui::Ui, advisories: &[&Advisory]) {
+    let mut kinds: Vec<AdvisoryKind> = advisories.iter().map(|advisory| advisory.kind).collect();
+    kinds.sort();
+    kinds.dedup();
+    for kind in kinds {
+        let of_kind: Vec<String> = advisories
+            .iter()
+            .filter(|advisory| advisory.kind == kind)
+            .map(|advisory| format!("{}: {}", advisory.id, advisory.title))
+            .collect();
+        advisory_kind_badge(ui, kind).on_hover_text(of_kind.join("\n"));
+    }
+}
@@ -0,0 +1,70 @@
+use {
+    eframe::egui,
+    std::{
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        time::{Duration, SystemTime},
+    },
+};
+
+/// How often the watched files are checked for modifications
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Detects modifications of a set of files by polling their modification times on a separate
+/// thread, which requests a repaint when something changed
+pub struct FileWatcher {
+    changed: Arc<AtomicBool>,
+    /// Tells the thread to stop
+    stop: Arc<AtomicBool>,
+}
+
+impl FileWatcher {
+    pub fn new(paths: impl IntoIterator<Item = PathBuf>, ctx: &egui::Context) -> Self {
+        let mut files: Vec<(PathBuf, Option<SystemTime>)> = paths
+            .into_iter()
+            .map(|path| {
+                let mtime = mtime(&path);
+                (path, mtime)
+            })
+            .collect();
+        let changed = Arc::new(AtomicBool::new(false));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (thread_changed, thread_stop) = (changed.clone(), stop.clone());
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            while !thread_stop.load(Ordering::Relaxed) {
+                std::thread::sleep(POLL_INTERVAL);
+                let mut any_changed = false;
+                for (path, last_mtime) in &mut files {
+                    let mtime = mtime(path);
+                    if mtime != *last_mtime {
+                        *last_mtime = mtime;
+                        any_changed = true;
+                    }
+                }
+                if any_changed {
+                    thread_changed.store(true, Ordering::Relaxed);
+                    ctx.request_repaint();
+                }
+            }
+        });
+        Self { changed, stop }
+    }
+    /// Returns whether any of the files changed since the last call
+    pub fn poll(&mut self) -> bool {
+        self.changed.swap(false, Ordering::Relaxed)
+    }
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn mtime(path: &PathBuf) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}